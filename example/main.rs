@@ -26,7 +26,8 @@ fn main() {
 
     let font_set = FontSetBuilder::new()
         .push(Font::from_data(&mut font_bts).expect("couldn't parse font data"))
-        .build();
+        .build()
+        .expect("no font in the font set");
 
     let image = image::load_from_memory(test_image).expect("image load failed");
     let output_dimensions = Dimensions::from_wh(1000, 1000);
@@ -39,7 +40,8 @@ fn main() {
         .build()
         .unwrap();
 
-        wc.write_content(ranked, 2000);
+    wc.write_content(ranked, 2000)
+        .expect("couldn't write words");
     wc.export_text_to_file("created.svg")
         .expect("couldn't export");
 
@@ -1,12 +1,12 @@
-use crate::common::svg_command::{Curve, End, Line, Move, QuadCurve, SVGPathCommand};
+use crate::common::svg_command::{Curve, End, Line, Move, PathEncoder, QuadCurve, SVGPathCommand};
 use crate::types::point::Point;
 use crate::types::rect::Rect;
 use crate::types::rotation::Rotation;
 
 #[derive(Debug, Clone)]
 pub(crate) struct Letter {
-    #[allow(dead_code)]
     pub(crate) char: char,
+    pub(crate) glyph_id: u16,
     pub(crate) pixel_bounding_box: Rect<f32>,
 
     pub(crate) offset: Point<f32>,
@@ -21,12 +21,14 @@ pub(crate) struct Letter {
 impl Letter {
     pub fn new(
         char: char,
+        glyph_id: u16,
         pixel_bounding_box: Rect<f32>,
         offset: Point<f32>,
         rotation: Rotation,
     ) -> Self {
         Self {
             char,
+            glyph_id,
             pixel_bounding_box,
             offset,
             cursor: Point::default(),
@@ -69,13 +71,55 @@ impl Letter {
         }
     }
 
-    pub(crate) fn d(&self, global_off: &Point<f32>) -> String {
+    pub(crate) fn encode_path(&self, global_off: &Point<f32>, encoder: &mut PathEncoder) {
         let off: Point<f32> = self.rotation.rotate_point(self.offset) + *global_off;
-        let mut d = String::with_capacity(self.state.iter().map(|x| x.length_estimation()).sum());
         for x in &self.state {
-            x.append_to_string(&off, &mut d);
+            x.encode(&off, encoder);
         }
-        d
+    }
+
+    /**
+        Closed outlines of the glyph in absolute coordinates, with curves approximated by lines,
+        which are at most `tolerance` away from the curve.
+    */
+    pub(crate) fn polygons(&self, global_off: &Point<f32>, tolerance: f32) -> Vec<Vec<Point<f32>>> {
+        let off: Point<f32> = self.rotation.rotate_point(self.offset) + *global_off;
+        let mut polygons = Vec::new();
+        let mut current = Vec::new();
+
+        for command in &self.state {
+            match command {
+                SVGPathCommand::Move(m) => {
+                    if current.len() > 1 {
+                        polygons.push(std::mem::take(&mut current));
+                    }
+                    current.clear();
+                    current.push(m.position + off);
+                }
+                SVGPathCommand::Line(l) => current.push(l.end + off),
+                SVGPathCommand::QuadCurve(q) => current.extend(
+                    q.approximate_with_tolerance(tolerance)
+                        .iter()
+                        .map(|l| l.end + off),
+                ),
+                SVGPathCommand::Curve(c) => current.extend(
+                    c.approximate_with_tolerance(tolerance)
+                        .iter()
+                        .map(|l| l.end + off),
+                ),
+                SVGPathCommand::End(_) => {
+                    if current.len() > 1 {
+                        polygons.push(std::mem::take(&mut current));
+                    }
+                    current.clear();
+                }
+            }
+        }
+        if current.len() > 1 {
+            polygons.push(current);
+        }
+
+        polygons
     }
 
     pub(crate) fn simplify(&mut self) {
@@ -1,5 +1,7 @@
 pub(crate) mod letter;
+pub(crate) mod style;
 pub(crate) mod word;
 pub(crate) mod word_cloud;
 
-pub use crate::cloud::word_cloud::{WordCloud, WordCloudBuilder};
+pub use crate::cloud::style::{PlacedWordInfo, WordStyle};
+pub use crate::cloud::word_cloud::{Layout, WordCloud, WordCloudBuilder, WordContent};
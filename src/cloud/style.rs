@@ -0,0 +1,111 @@
+use crate::cloud::word::Word;
+use crate::color::Color;
+use crate::export::escape_xml;
+use svg::Node;
+
+/**
+    Information about a word, after it has been placed on the [`crate::WordCloud`]. Passed to
+    the style hook set with [`crate::WordCloudBuilder::style`].
+*/
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct PlacedWordInfo<'w> {
+    /// The word itself
+    pub text: &'w str,
+    /// Number of occurrences in the [`crate::RankedWords`]
+    pub count: usize,
+    /// Position in the [`crate::RankedWords`], starting with `0` for the most common word
+    pub rank: usize,
+    /// Count relative to the most common word, in `0.0..=1.0`
+    pub weight: f32,
+    /// Name of the used font
+    pub font: &'w str,
+    /// Font size in pixels
+    pub font_size: f32,
+    /// Clockwise rotation in degrees
+    pub rotation: f32,
+    /// Horizontal position of the baseline origin
+    pub x: f32,
+    /// Vertical position of the baseline origin
+    pub y: f32,
+    /// Width of the bounding box
+    pub width: f32,
+    /// Height of the bounding box
+    pub height: f32,
+    /// Region of the [`crate::RegionMask`] the word was written to
+    pub region: Option<usize>,
+    /// The color the word would have without the style hook
+    pub color: Option<Color>,
+}
+
+impl<'w> PlacedWordInfo<'w> {
+    pub(crate) fn new(word: &'w Word, color: Option<Color>) -> Self {
+        PlacedWordInfo {
+            text: &word.text,
+            count: word.count,
+            rank: word.rank,
+            weight: word.emphasis.weight,
+            font: word.used_font.name(),
+            font_size: word.scale,
+            rotation: word.rotation.inner(),
+            x: word.offset.x,
+            y: word.offset.y,
+            width: word.bounding_box.width(),
+            height: word.bounding_box.height(),
+            region: word.region,
+            color,
+        }
+    }
+}
+
+/**
+    Presentation attributes of a single exported word. Fields left at `None` are not emitted,
+    except for `fill`, which falls back to the default color of the word.
+
+    ```
+    use wordcloud::{WordCloudBuilder, WordStyle};
+    use wordcloud::color::Color;
+
+    let wc = WordCloudBuilder::new()
+        // ...
+        .style(|info| WordStyle {
+            fill: Some(if info.rank < 10 { Color::rgb(200, 0, 0) } else { Color::rgb(90, 90, 90) }),
+            opacity: Some(0.5 + info.weight / 2.),
+            ..Default::default()
+        })
+        .build();
+    ```
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordStyle {
+    pub fill: Option<Color>,
+    pub opacity: Option<f32>,
+    pub stroke: Option<Color>,
+    pub stroke_width: Option<f32>,
+    pub class: Option<String>,
+}
+
+impl WordStyle {
+    pub(crate) fn apply<N: Node>(&self, node: &mut N) {
+        if let Some(fill) = self.fill {
+            node.assign("fill", fill.to_string());
+            if fill.a != 255 {
+                node.assign("fill-opacity", fill.a as f32 / 255.);
+            }
+        }
+        if let Some(opacity) = self.opacity {
+            node.assign("opacity", opacity);
+        }
+        if let Some(stroke) = self.stroke {
+            node.assign("stroke", stroke.to_string());
+        }
+        if let Some(stroke_width) = self.stroke_width {
+            node.assign("stroke-width", stroke_width);
+        }
+        if let Some(class) = &self.class {
+            node.assign("class", escape_xml(class));
+        }
+    }
+}
+
+pub(crate) type StyleFn<'a> = Box<dyn Fn(&PlacedWordInfo) -> WordStyle + Send + Sync + 'a>;
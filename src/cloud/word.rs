@@ -1,15 +1,16 @@
 use std::cell::RefCell;
 use std::ops::Range;
 use swash::scale::ScaleContext;
-use swash::shape::Direction::LeftToRight;
+use swash::shape::Direction::{LeftToRight, RightToLeft};
 use swash::shape::ShapeContext;
 
 use swash::zeno::Command;
 use swash::zeno::PathData;
 
 use crate::cloud::letter::Letter;
+use crate::common::bidi::{visual_order, BidiLevels};
 use crate::common::font::{Font, GuessScript};
-use crate::common::svg_command::{Curve, End, Line, Move, QuadCurve, SVGPathCommand};
+use crate::common::svg_command::{Curve, End, Line, Move, PathEncoder, QuadCurve, SVGPathCommand};
 use crate::types::point::Point;
 use crate::types::rect::Rect;
 use crate::types::rotation::Rotation;
@@ -23,6 +24,34 @@ thread_local! {
 type WordBuildingError = String;
 type WordBuildingResult<T> = Result<T, WordBuildingError>;
 
+/**
+    Byte range of a word and the font it is shaped with
+*/
+pub(crate) type FontRun<'a> = (Range<usize>, &'a Font<'a>);
+
+/**
+    Part of a word, which was shaped with a single font
+*/
+#[derive(Clone)]
+pub(crate) struct TextRun<'a> {
+    /// Byte range in the text of the word
+    pub(crate) text: Range<usize>,
+    /// Range in the glyphs of the word
+    pub(crate) glyphs: Range<usize>,
+    pub(crate) font: &'a Font<'a>,
+}
+
+/**
+    How prominent a word is, from 0 for the least to 1 for the most prominent word of the cloud
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Emphasis {
+    /// Count of the word relative to the most frequent word
+    pub(crate) weight: f32,
+    /// Rank of the word, 1 for the first and 0 for the last word
+    pub(crate) rank: f32,
+}
+
 #[derive(Clone)]
 pub(crate) struct Word<'a> {
     pub(crate) text: String,
@@ -32,105 +61,187 @@ pub(crate) struct Word<'a> {
     pub(crate) bounding_box: Rect<f32>,
     pub(crate) scale: f32,
     pub(crate) rotation: Rotation,
+    /// Font of the first run, which stands for the whole word
     pub(crate) used_font: &'a Font<'a>,
+    /// Runs in visual order, from left to right
+    pub(crate) runs: Vec<TextRun<'a>>,
+    /// Whether the word is written from right to left
+    pub(crate) rtl: bool,
+    pub(crate) region: Option<usize>,
+
+    pub(crate) count: usize,
+    pub(crate) rank: usize,
+    pub(crate) emphasis: Emphasis,
 }
 
 impl<'a> Word<'a> {
     pub(crate) fn build(
         text: &str,
-        font: &'a Font<'a>,
+        runs: &[FontRun<'a>],
         font_size: f32,
         start: Point<f32>,
         rotation: Rotation,
+        emphasis: Emphasis,
     ) -> WordBuildingResult<Word<'a>> {
+        if runs.is_empty() {
+            return Err(format!("no font for the word {}", text));
+        }
+
         let mut w = SHAPER_TL.with(|shape_ref| {
             SCALER_TL.with(|scale_ref| {
                 let mut shape_context = shape_ref.borrow_mut();
-                let mut shaper = shape_context
-                    .builder(*font.reference())
-                    .script(text.guess_script().s())
-                    .size(font_size)
-                    .direction(LeftToRight)
-                    .features(&[
-                        ("lnum", 1),
-                        ("tnum", 1),
-                        ("pnum", 1),
-                        ("ordn", 1),
-                        ("swsh", 1),
-                        ("liga", 1),
-                        ("hlig", 1),
-                        ("dlig", 1),
-                        ("calt", 1),
-                    ])
-                    .build();
-
                 let mut scale_context = scale_ref.borrow_mut();
-                let mut scaler = scale_context
-                    .builder(*font.reference())
-                    .size(font_size)
-                    .build();
-
-                shaper.add_str(text);
 
                 let mut letters = Vec::new();
-                let chars = text.chars().collect::<Vec<char>>();
-                let mut char_index = 0;
+                let mut text_runs = Vec::with_capacity(runs.len());
                 let mut advance = 0.0;
 
-                shaper.shape_with(|c| {
-                    letters.append(
-                        &mut c
-                            .glyphs
-                            .iter()
-                            .map(|glyph| {
-                                let outline =
-                                    scaler.scale_outline(glyph.id).unwrap_or_default();
-
-                                let bounds = outline.bounds();
-                                let bbox = Rect {
-                                    min: Point {
-                                        x: bounds.min.x + advance,
-                                        y: bounds.min.y,
-                                    },
-                                    max: Point {
-                                        x: bounds.max.x + advance,
-                                        y: bounds.max.y,
-                                    },
-                                };
-
-                                let mut letter = Letter::new(
-                                    chars[char_index],
-                                    bbox,
-                                    Point {
-                                        x: glyph.x + advance,
-                                        y: glyph.y,
-                                    },
-                                    rotation,
-                                );
-
-                                let commands = outline.path();
-                                for command in commands.commands() {
-                                    let cmd: Command = command;
-                                    match cmd {
-                                        Command::MoveTo(p) => letter.move_to(p.x, p.y),
-                                        Command::LineTo(p) => letter.line_to(p.x, p.y),
-                                        Command::CurveTo(x2, x1, x) => {
-                                            letter.curve_to(x2.x, x2.y, x1.x, x1.y, x.x, x.y)
-                                        }
-                                        Command::QuadTo(x1, x) => {
-                                            letter.quad_to(x1.x, x1.y, x.x, x.y)
-                                        }
-                                        Command::Close => letter.close(),
+                // the font runs are split where the direction changes and laid out in visual order,
+                // every segment is shaped with its own font, continuing where the previous one ended
+                let bidi = BidiLevels::resolve(text);
+                let mut segments: Vec<(Range<usize>, &'a Font<'a>, u8)> = Vec::new();
+                for (range, font) in runs {
+                    for ((start, _), level) in text.char_indices().zip(&bidi.levels) {
+                        if !range.contains(&start) {
+                            continue;
+                        }
+                        match segments.last_mut() {
+                            Some((r, f, l))
+                                if *l == *level && std::ptr::eq(*f, *font) && r.end == start => {}
+                            _ => segments.push((start..start, font, *level)),
+                        }
+                        if let Some((r, _, _)) = segments.last_mut() {
+                            r.end = text[start..]
+                                .chars()
+                                .next()
+                                .map_or(start, |c| start + c.len_utf8());
+                        }
+                    }
+                }
+                let order = visual_order(&segments.iter().map(|s| s.2).collect::<Vec<u8>>());
+
+                // vertical text uses the vertical alternates of the glyphs and is stacked with
+                // the vertical advances, centered on the x of the word
+                let vertical = rotation == Rotation::Vertical;
+                let features: &[(&str, u16)] = &[
+                    ("lnum", 1),
+                    ("tnum", 1),
+                    ("pnum", 1),
+                    ("ordn", 1),
+                    ("swsh", 1),
+                    ("liga", 1),
+                    ("hlig", 1),
+                    ("dlig", 1),
+                    ("calt", 1),
+                    ("vert", 1),
+                    ("vrt2", 1),
+                ];
+                let features = if vertical { features } else { &features[..9] };
+
+                for (range, font, level) in order.iter().map(|i| &segments[*i]) {
+                    let run_text = &text[range.clone()];
+                    let variations = font.variations(emphasis.weight, emphasis.rank);
+                    let mut shaper = shape_context
+                        .builder(*font.reference())
+                        .script(run_text.guess_script().s())
+                        .size(font_size)
+                        .direction(if level % 2 == 1 {
+                            RightToLeft
+                        } else {
+                            LeftToRight
+                        })
+                        .features(features)
+                        .variations(&variations)
+                        .build();
+
+                    let mut scaler = scale_context
+                        .builder(*font.reference())
+                        .size(font_size)
+                        .variations(&variations)
+                        .build();
+                    let coords = font
+                        .reference()
+                        .variations()
+                        .normalized_coords(&variations)
+                        .collect::<Vec<_>>();
+                    let metrics = font.reference().glyph_metrics(&coords).scale(font_size);
+
+                    shaper.add_str(run_text);
+
+                    // the shaper emits the clusters in logical order, right-to-left text is reversed
+                    let mut clusters = Vec::new();
+                    shaper.shape_with(|c| {
+                        // the first character of the cluster stands for all of its glyphs
+                        let char = run_text[c.source.start as usize..]
+                            .chars()
+                            .next()
+                            .unwrap_or_default();
+                        clusters.push((char, c.glyphs.to_vec()));
+                    });
+                    if level % 2 == 1 {
+                        clusters.reverse();
+                    }
+
+                    let first_glyph = letters.len();
+                    for (char, glyphs) in clusters {
+                        for glyph in glyphs {
+                            let outline = scaler.scale_outline(glyph.id).unwrap_or_default();
+
+                            let position = if vertical {
+                                Point {
+                                    x: glyph.x - metrics.advance_width(glyph.id) / 2.,
+                                    y: advance + metrics.vertical_origin(glyph.id),
+                                }
+                            } else {
+                                Point {
+                                    x: glyph.x + advance,
+                                    y: glyph.y,
+                                }
+                            };
+
+                            let bounds = outline.bounds();
+                            let bbox = Rect {
+                                min: Point {
+                                    x: bounds.min.x + position.x - glyph.x,
+                                    y: bounds.min.y,
+                                },
+                                max: Point {
+                                    x: bounds.max.x + position.x - glyph.x,
+                                    y: bounds.max.y,
+                                },
+                            };
+
+                            let mut letter = Letter::new(char, glyph.id, bbox, position, rotation);
+
+                            let commands = outline.path();
+                            for command in commands.commands() {
+                                let cmd: Command = command;
+                                match cmd {
+                                    Command::MoveTo(p) => letter.move_to(p.x, p.y),
+                                    Command::LineTo(p) => letter.line_to(p.x, p.y),
+                                    Command::CurveTo(x2, x1, x) => {
+                                        letter.curve_to(x2.x, x2.y, x1.x, x1.y, x.x, x.y)
                                     }
+                                    Command::QuadTo(x1, x) => letter.quad_to(x1.x, x1.y, x.x, x.y),
+                                    Command::Close => letter.close(),
                                 }
-                                advance += glyph.advance;
-                                char_index += 1;
+                            }
+                            advance += if vertical {
+                                metrics.advance_height(glyph.id)
+                            } else {
+                                glyph.advance
+                            };
+
+                            letters.push(letter);
+                        }
+                    }
 
-                                letter
-                            })
-                            .collect::<Vec<Letter>>(),
-                    );
-                });
+                    text_runs.push(TextRun {
+                        text: range.clone(),
+                        glyphs: first_glyph..letters.len(),
+                        font,
+                    });
+                }
 
                 Word {
                     text: String::from(text),
@@ -139,7 +250,13 @@ impl<'a> Word<'a> {
                     bounding_box: Rect::default(),
                     scale: font_size,
                     rotation,
-                    used_font: font,
+                    used_font: runs[0].1,
+                    runs: text_runs,
+                    rtl: bidi.rtl,
+                    region: None,
+                    count: 0,
+                    rank: 0,
+                    emphasis,
                 }
             })
         });
@@ -207,6 +324,36 @@ impl<'a> Word<'a> {
         Ok(w)
     }
 
+    /**
+        Builds the same word again with a different size and rotation, keeping everything
+        that isn't related to its geometry.
+    */
+    pub(crate) fn rebuild(
+        &self,
+        font_size: f32,
+        start: Point<f32>,
+        rotation: Rotation,
+    ) -> WordBuildingResult<Word<'a>> {
+        let mut runs = self
+            .runs
+            .iter()
+            .map(|r| (r.text.clone(), r.font))
+            .collect::<Vec<FontRun>>();
+        runs.sort_by_key(|(range, _)| range.start);
+        let mut w = Word::build(
+            self.text.as_str(),
+            &runs,
+            font_size,
+            start,
+            rotation,
+            self.emphasis,
+        )?;
+        w.region = self.region;
+        w.count = self.count;
+        w.rank = self.rank;
+        Ok(w)
+    }
+
     fn recalculate_bounding_box(&mut self) {
         if self.glyphs.is_empty() {
             return;
@@ -219,26 +366,29 @@ impl<'a> Word<'a> {
 
         let mut max_y = 0.0;
         let mut min_y = f32::MAX;
+        let (mut min_x, mut max_x) = (
+            first_glyph.pixel_bounding_box.min.x,
+            last_glyph.pixel_bounding_box.max.x,
+        );
 
         for glyph in &self.glyphs {
-            let bbox = glyph.pixel_bounding_box;
+            let bbox = glyph.relative_bounding_box(&Rotation::Zero);
             if bbox.max.y > max_y {
                 max_y = bbox.max.y;
             }
             if bbox.min.y < min_y {
                 min_y = bbox.min.y;
             }
+            // stacked glyphs don't grow from left to right
+            if self.rotation == Rotation::Vertical {
+                min_x = min_x.min(bbox.min.x);
+                max_x = max_x.max(bbox.max.x);
+            }
         }
 
         let base_rect: Rect<f32> = Rect {
-            min: Point {
-                x: first_glyph.pixel_bounding_box.min.x,
-                y: min_y,
-            },
-            max: Point {
-                x: last_glyph.pixel_bounding_box.max.x,
-                y: max_y,
-            },
+            min: Point { x: min_x, y: min_y },
+            max: Point { x: max_x, y: max_y },
         };
 
         let rotated = self.rotation.rotate_rectangle(base_rect);
@@ -264,8 +414,22 @@ impl<'a> Word<'a> {
             .flat_map(|x| x.absolute_collidables(&self.rotation, self.offset))
     }
 
-    pub(crate) fn d(&self) -> String {
-        self.glyphs.iter().map(|g| g.d(&self.offset)).collect()
+    pub(crate) fn d(&self, precision: usize) -> String {
+        let mut encoder = PathEncoder::new(precision);
+        for glyph in &self.glyphs {
+            glyph.encode_path(&self.offset, &mut encoder);
+        }
+        encoder.finish()
+    }
+
+    /**
+        Closed outlines of all glyphs in absolute coordinates, see [`Letter::polygons`].
+    */
+    pub(crate) fn polygons(&self, tolerance: f32) -> Vec<Vec<Point<f32>>> {
+        self.glyphs
+            .iter()
+            .flat_map(|g| g.polygons(&self.offset, tolerance))
+            .collect()
     }
 
     pub(crate) fn word_intersect(&self, other: &Word) -> bool {
@@ -357,8 +521,12 @@ impl<'a> Word<'a> {
 pub(crate) struct WordBuilder<'a> {
     content: Option<String>,
     scale: Option<f32>,
-    font: Option<&'a Font<'a>>,
+    runs: Vec<FontRun<'a>>,
     start: Option<Point<f32>>,
+    region: Option<usize>,
+    count: usize,
+    rank: usize,
+    emphasis: Emphasis,
 }
 
 impl<'a> WordBuilder<'a> {
@@ -374,8 +542,8 @@ impl<'a> WordBuilder<'a> {
         self.scale = Some(scale);
         self
     }
-    pub(crate) fn font(mut self, font: &'a Font<'a>) -> Self {
-        self.font = Some(font);
+    pub(crate) fn runs(mut self, runs: Vec<FontRun<'a>>) -> Self {
+        self.runs = runs;
         self
     }
     pub(crate) fn start(mut self, start: Point<f32>) -> Self {
@@ -383,15 +551,40 @@ impl<'a> WordBuilder<'a> {
         self
     }
 
+    pub(crate) fn region(mut self, region: Option<usize>) -> Self {
+        self.region = region;
+        self
+    }
+
+    pub(crate) fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub(crate) fn rank(mut self, rank: usize) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    pub(crate) fn emphasis(mut self, emphasis: Emphasis) -> Self {
+        self.emphasis = emphasis;
+        self
+    }
+
     pub(crate) fn build(&self) -> WordBuildingResult<Word<'a>> {
         #[allow(clippy::unwrap_used)]
-        Word::build(
+        let mut word = Word::build(
             self.content.as_ref().unwrap(),
-            self.font.unwrap(),
+            &self.runs,
             self.scale.unwrap(),
             self.start.unwrap(),
             Rotation::Zero,
-        )
+            self.emphasis,
+        )?;
+        word.region = self.region;
+        word.count = self.count;
+        word.rank = self.rank;
+        Ok(word)
     }
 }
 
@@ -403,7 +596,15 @@ fn test() {
     font.extend_from_slice(include_bytes!("../../example/assets/OpenSans-Regular.ttf"));
     let f = Font::from_data(&mut font).unwrap();
 
-    let mut word = Word::build("yeah", &f, 100., (0., 100.).into(), Rotation::Zero).unwrap();
+    let mut word = Word::build(
+        "yeah",
+        &[(0..4, &f)],
+        100.,
+        (0., 100.).into(),
+        Rotation::Zero,
+        Emphasis::default(),
+    )
+    .unwrap();
     word.move_word(&(189., 333.).into());
 
     let mut document = svg::Document::new()
@@ -414,7 +615,7 @@ fn test() {
     let p = svg::node::element::Path::new()
         .set("stroke", "black")
         .set("stroke-width", 1)
-        .set("d", word.d());
+        .set("d", word.d(2));
 
     let p2 = svg::node::element::Rectangle::new()
         .set("stroke", "green")
@@ -441,3 +642,38 @@ fn test() {
 
     svg::save("test.svg", &document).expect("TODO: panic message");
 }
+
+#[test]
+fn test_vertical() {
+    let mut font = Vec::from(include_bytes!("../../example/assets/OpenSans-Regular.ttf") as &[u8]);
+    let f = Font::from_data(&mut font).unwrap();
+
+    let horizontal = Word::build(
+        "IIII",
+        &[(0..4, &f)],
+        50.,
+        (100., 100.).into(),
+        Rotation::Zero,
+        Emphasis::default(),
+    )
+    .unwrap();
+    let word = Word::build(
+        "IIII",
+        &[(0..4, &f)],
+        50.,
+        (100., 100.).into(),
+        Rotation::Vertical,
+        Emphasis::default(),
+    )
+    .unwrap();
+
+    // the glyphs are stacked below the start, centered on its x
+    assert!(word.bounding_box.height() > horizontal.bounding_box.height() * 3.);
+    assert!(word.bounding_box.width() < horizontal.bounding_box.width() / 3.);
+    assert!(word.bounding_box.min.y >= 100.);
+    assert!((word.bounding_box.min.x + word.bounding_box.max.x - 200.).abs() < 1.);
+    assert!(word
+        .glyphs
+        .windows(2)
+        .all(|g| g[0].offset.y < g[1].offset.y));
+}
@@ -1,11 +1,17 @@
-use crate::cloud::word::{Word, WordBuilder};
-use crate::common::font::FontSet;
-use std::io::Cursor;
+use crate::cloud::style::{PlacedWordInfo, StyleFn, WordStyle};
+use crate::cloud::word::{Emphasis, Word, WordBuilder};
+use crate::common::font::{Font, FontSelection, FontSelectionInfo, FontSet};
+use crate::export::subset::embedded_font;
+use crate::export::svg_writer::SvgWriter;
+use crate::export::{escape_xml, format_number, ExportOptions, RenderedOptions};
+use std::fs::File;
 use std::io::Error;
+use std::io::{BufWriter, Write};
 
 #[cfg(feature = "background_image")]
-use crate::image::{average_color_for_rect, canny_algorithm, color_to_rgb_string};
+use crate::image::{average_color_for_rect, canny_algorithm, RegionMap, RegionMask};
 use crate::types::point::Point;
+use crate::types::polyline::Polyline;
 use crate::types::rect::Rect;
 use crate::types::rotation::Rotation;
 use base64::engine::general_purpose::STANDARD_NO_PAD;
@@ -17,26 +23,38 @@ use image::imageops::grayscale;
 use image::{DynamicImage, GenericImageView, Rgba};
 
 use itertools::Itertools;
-use parking_lot::{Mutex, RwLock};
+use parking_lot::RwLock;
 use quadtree_rs::area::{Area, AreaBuilder};
 
 use quadtree_rs::Quadtree;
 use rand::thread_rng;
 use rand::Rng;
 use rayon::iter::ParallelIterator;
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator};
 
-use std::sync::Arc;
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Range;
 
-use crate::font::GuessScript;
+use crate::color::{Color, ColorScheme};
 use crate::rank::RankedWords;
 use crate::types::spiral::Spiral;
 use crate::Dimensions;
-use svg::node::element::{Group, Path, Rectangle, Style, Text};
+use svg::node::element::{Description, Group, Path, Rectangle, Style, Text, Title};
 use svg::{Document, Node};
 
 const QUADTREE_DIVISOR: f32 = 4.;
 
+/// Contours made up of fewer background cells than this are ignored by [`Layout::Outline`]
+const MIN_CONTOUR_CELLS: usize = 8;
+/// Spacing between two words placed along the same contour
+const CONTOUR_WORD_GAP: f32 = 8.;
+/// Number of words per thread, whose paths are built at once while streaming the export
+const RENDER_BATCH_SIZE: usize = 16;
+
+const TITLE_ID: &str = "wordcloud-title";
+const DESCRIPTION_ID: &str = "wordcloud-desc";
+const WORD_LIST_ID: &str = "wordcloud-words";
+
 macro_rules! available_parallelism {
     () => {
         match std::thread::available_parallelism() {
@@ -49,15 +67,76 @@ macro_rules! available_parallelism {
 #[cfg(not(feature = "background_image"))]
 type DynamicImage = ();
 
+/**
+    Decides how the words are arranged on the canvas
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Words fill the free space of the canvas, avoiding the edges of the background image.
+    #[default]
+    Fill,
+    /// Words are laid along the edges detected in the background image, rotated to follow
+    /// the contour, so the text traces the silhouette. Requires a background image.
+    Outline,
+}
+
+/**
+    Words written with [`WordCloud::write_content`], either spread over the whole canvas or
+    split into one [`RankedWords`] per region of the [`RegionMask`]
+
+    ```no_run
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, RankedWords, RegionMask, WordCloudBuilder};
+    # use image::Rgba;
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let mask_image = image::open("mask.png").unwrap();
+    # let mask = RegionMask::new(&mask_image)
+    #     .region(0, Rgba([255, 0, 0, 255]))
+    #     .region(1, Rgba([0, 0, 255, 255]));
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).regions(mask).build().unwrap();
+    # let words = |text: &str| RankedWords::rank(text.split_whitespace().map(String::from).collect());
+    # let (ranked, pros, cons) = (words("a b"), words("fast safe"), words("slow odd"));
+    // words for the whole canvas
+    wc.write_content(ranked, 2000).unwrap();
+    // the pros on the left half and the cons on the right half of the mask
+    wc.write_content(vec![(0, pros), (1, cons)], 500).unwrap();
+    ```
+*/
+pub enum WordContent {
+    Words(RankedWords),
+    Regions(Vec<(usize, RankedWords)>),
+}
+
+impl From<RankedWords> for WordContent {
+    fn from(words: RankedWords) -> Self {
+        WordContent::Words(words)
+    }
+}
+
+impl From<Vec<(usize, RankedWords)>> for WordContent {
+    fn from(regions: Vec<(usize, RankedWords)>) -> Self {
+        WordContent::Regions(regions)
+    }
+}
+
 /**
     Creates the WordCloud
 */
 pub struct WordCloud<'a> {
-    ct: RwLock<Quadtree<u64, Word<'a>>>,
+    pub(crate) ct: RwLock<Quadtree<u64, Word<'a>>>,
     bg: Option<Quadtree<u64, ()>>,
     bg_image: Option<&'a DynamicImage>,
-    dimensions: Dimensions,
+    pub(crate) dimensions: Dimensions,
     font: &'a FontSet<'a>,
+    font_selection: FontSelection<'a>,
+    layout: Layout,
+    vertical_writing: bool,
+    #[cfg(feature = "background_image")]
+    regions: Option<RegionMap>,
+    color_scheme: Option<ColorScheme>,
+    style: Option<StyleFn<'a>>,
 }
 
 impl<'a> WordCloud<'a> {
@@ -76,6 +155,13 @@ impl<'a> WordCloud<'a> {
             bg_image: None,
             dimensions,
             font,
+            font_selection: FontSelection::default(),
+            layout: Layout::Fill,
+            vertical_writing: false,
+            #[cfg(feature = "background_image")]
+            regions: None,
+            color_scheme: None,
+            style: None,
         }
     }
 
@@ -134,6 +220,129 @@ impl<'a> WordCloud<'a> {
         }
     }
 
+    fn search_region(word: &Word) -> Area<u64> {
+        AreaBuilder::default()
+            .anchor(quadtree_rs::point::Point {
+                x: f32::max((word.bounding_box.min.x / QUADTREE_DIVISOR).ceil() - 1., 0.) as u64,
+                y: f32::max((word.bounding_box.min.y / QUADTREE_DIVISOR).ceil() - 1., 0.) as u64,
+            })
+            .dimensions((
+                (word.bounding_box.width() / QUADTREE_DIVISOR).ceil() as u64 + 2,
+                (word.bounding_box.height() / QUADTREE_DIVISOR).ceil() as u64 + 2,
+            ))
+            .build()
+            .expect("search region undefined")
+    }
+
+    fn insert_region(word: &Word) -> Area<u64> {
+        AreaBuilder::default()
+            .anchor(
+                (
+                    (word.bounding_box.min.x / QUADTREE_DIVISOR).ceil() as u64,
+                    (word.bounding_box.min.y / QUADTREE_DIVISOR).ceil() as u64,
+                )
+                    .into(),
+            )
+            .dimensions((
+                (word.bounding_box.width() / QUADTREE_DIVISOR).ceil() as u64,
+                (word.bounding_box.height() / QUADTREE_DIVISOR).ceil() as u64,
+            ))
+            .build()
+            .expect("insert region undefined")
+    }
+
+    /**
+        Inserts the word at its current position, if it doesn't collide with any already placed
+        word (and optionally the background). Returns the word back if it couldn't be placed.
+    */
+    #[allow(clippy::result_large_err)]
+    fn try_insert(&self, word: Word<'a>, check_background: bool) -> Result<(), Word<'a>> {
+        let search_region = WordCloud::search_region(&word);
+        let insert_region = WordCloud::insert_region(&word);
+
+        if check_background {
+            if let Some(qt_bg) = &self.bg {
+                if qt_bg.query(insert_region).next().is_some() {
+                    return Err(word);
+                }
+            }
+
+            #[cfg(feature = "background_image")]
+            if let (Some(map), Some(region)) = (&self.regions, word.region) {
+                if !map.area_inside(&insert_region, region) {
+                    return Err(word);
+                }
+            }
+        }
+
+        let len_bf = {
+            let read = self.ct.read();
+
+            for result in read.query(search_region) {
+                if word.word_intersect(result.value_ref()) {
+                    return Err(word);
+                }
+            }
+            read.len()
+        };
+
+        let mut write = self.ct.write();
+        // read the newly added handles
+        for handle_id in len_bf..=write.len() {
+            if let Some(new_entry) = write.get(handle_id as u64) {
+                if word.word_intersect(new_entry.value_ref()) {
+                    return Err(word);
+                }
+            }
+        }
+        if write.insert(insert_region, word).is_none() {
+            panic!("insertion failed");
+        }
+
+        Ok(())
+    }
+
+    /**
+        Range of valid positions for the word. Words belonging to a region are kept inside
+        the bounding box of that region.
+    */
+    fn positioning_range(&self, word: &Word) -> (Range<f32>, Range<f32>) {
+        let (x_range, y_range) = word.get_positioning_range(&self.dimensions);
+
+        #[cfg(feature = "background_image")]
+        if let (Some(map), Some(region)) = (&self.regions, word.region) {
+            if let Some(bounds) = map.bounds(region) {
+                let normalized = word.normalized_bbox();
+                let x = f32::max(
+                    x_range.start,
+                    bounds.min.x as f32 * QUADTREE_DIVISOR - normalized.min.x,
+                )
+                    ..f32::min(
+                        x_range.end,
+                        bounds.max.x as f32 * QUADTREE_DIVISOR
+                            - normalized.width()
+                            - normalized.min.x,
+                    );
+                let y = f32::max(
+                    y_range.start,
+                    bounds.min.y as f32 * QUADTREE_DIVISOR - normalized.min.y,
+                )
+                    ..f32::min(
+                        y_range.end,
+                        bounds.max.y as f32 * QUADTREE_DIVISOR
+                            - normalized.height()
+                            - normalized.min.y,
+                    );
+
+                if !x.is_empty() && !y.is_empty() {
+                    return (x, y);
+                }
+            }
+        }
+
+        (x_range, y_range)
+    }
+
     fn add_word(&self, mut word: Word<'a>) {
         let mut spiral = Spiral::new(5.);
         let mut iters = 0;
@@ -141,77 +350,9 @@ impl<'a> WordCloud<'a> {
         let mut break_flag = false;
         loop {
             if self.converted_dimensions().contains(&word.bounding_box) {
-                let mut intersected: bool = false;
-
-                let search_region = AreaBuilder::default()
-                    .anchor(quadtree_rs::point::Point {
-                        x: f32::max((word.bounding_box.min.x / QUADTREE_DIVISOR).ceil() - 1., 0.)
-                            as u64,
-                        y: f32::max((word.bounding_box.min.y / QUADTREE_DIVISOR).ceil() - 1., 0.)
-                            as u64,
-                    })
-                    .dimensions((
-                        (word.bounding_box.width() / QUADTREE_DIVISOR).ceil() as u64 + 2,
-                        (word.bounding_box.height() / QUADTREE_DIVISOR).ceil() as u64 + 2,
-                    ))
-                    .build()
-                    .expect("search region undefined");
-
-                let insert_region = AreaBuilder::default()
-                    .anchor(
-                        (
-                            (word.bounding_box.min.x / QUADTREE_DIVISOR).ceil() as u64,
-                            (word.bounding_box.min.y / QUADTREE_DIVISOR).ceil() as u64,
-                        )
-                            .into(),
-                    )
-                    .dimensions((
-                        (word.bounding_box.width() / QUADTREE_DIVISOR).ceil() as u64,
-                        (word.bounding_box.height() / QUADTREE_DIVISOR).ceil() as u64,
-                    ))
-                    .build()
-                    .expect("insert region undefined");
-
-                if let Some(qt_bg) = &self.bg {
-                    if qt_bg.query(insert_region).next().is_some() {
-                        intersected = true;
-                    }
-                }
-
-                let len_bf = if !intersected {
-                    let read = self.ct.read();
-
-                    for result in read.query(search_region) {
-                        if word.word_intersect(result.value_ref()) {
-                            intersected = true;
-                            break;
-                        }
-                    }
-                    read.len()
-                } else {
-                    0
-                };
-
-                if !intersected {
-                    let mut write = self.ct.write();
-                    // read the newly added handles
-                    for handle_id in len_bf..=write.len() {
-                        if let Some(new_entry) = write.get(handle_id as u64) {
-                            if word.word_intersect(new_entry.value_ref()) {
-                                intersected = true;
-                                break;
-                            }
-                        }
-                    }
-                    if !intersected {
-                        match write.insert(insert_region, word) {
-                            None => {
-                                panic!("insertion failed");
-                            }
-                            Some(_) => {}
-                        }
-                        break;
-                    }
+                match self.try_insert(word, true) {
+                    Ok(()) => break,
+                    Err(w) => word = w,
                 }
             } else {
                 println!(
@@ -224,7 +365,7 @@ impl<'a> WordCloud<'a> {
 
             spiral.advance();
             let incoming_pos = spiral.position() + word.offset;
-            let ranges = word.get_positioning_range(&self.dimensions);
+            let ranges = self.positioning_range(&word);
 
             if iters % 10 == 0
                 || !ranges.0.contains(&incoming_pos.x)
@@ -259,16 +400,11 @@ impl<'a> WordCloud<'a> {
                     }
                     break_flag = true;
                 } else {
-                    word = match Word::build(
-                        word.text.as_str(),
-                        word.used_font,
-                        word.scale - 5.,
-                        word.offset,
-                        Rotation::random(),
-                    ) {
+                    let rotation = self.rotation_for(&word.text);
+                    word = match word.rebuild(word.scale - 5., word.offset, rotation) {
                         Ok(mut w) => {
                             if !self.converted_dimensions().contains(&w.bounding_box) {
-                                let (xr, yr) = w.get_positioning_range(&self.dimensions);
+                                let (xr, yr) = self.positioning_range(&w);
                                 let point1 = Point {
                                     x: thread_rng().gen_range(xr.clone()),
                                     y: thread_rng().gen_range(yr.clone()),
@@ -287,6 +423,160 @@ impl<'a> WordCloud<'a> {
         }
     }
 
+    /**
+        Random rotation for a word, which is turned into [`Rotation::Vertical`] for CJK words
+        with [`WordCloudBuilder::vertical_writing`]
+    */
+    fn rotation_for(&self, text: &str) -> Rotation {
+        let rotation = Rotation::random();
+        let sideways = matches!(rotation, Rotation::Ninety | Rotation::TwoSeventy);
+        if sideways && self.vertical_writing && is_vertical_script(text) {
+            Rotation::Vertical
+        } else {
+            rotation
+        }
+    }
+
+    /**
+        Traces the cells of the background quadtree into ordered polylines, longest first.
+    */
+    fn contour_polylines(&self) -> Vec<Polyline> {
+        const NEIGHBOURS: [(i64, i64); 8] = [
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (0, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+            (1, -1),
+        ];
+
+        let bg = match &self.bg {
+            None => return Vec::new(),
+            Some(bg) => bg,
+        };
+
+        let mut remaining = BTreeSet::new();
+        for entry in bg.iter() {
+            let (anchor, area) = (entry.anchor(), entry.area());
+            for x in anchor.x..anchor.x + area.width() {
+                for y in anchor.y..anchor.y + area.height() {
+                    remaining.insert((x as i64, y as i64));
+                }
+            }
+        }
+
+        let mut contours = Vec::new();
+        while let Some(start) = remaining.pop_first() {
+            let mut chain = VecDeque::from([start]);
+            for forward in [true, false] {
+                loop {
+                    #[allow(clippy::unwrap_used)]
+                    let end = if forward {
+                        *chain.back().unwrap()
+                    } else {
+                        *chain.front().unwrap()
+                    };
+                    let next = NEIGHBOURS
+                        .iter()
+                        .map(|(dx, dy)| (end.0 + dx, end.1 + dy))
+                        .find(|cell| remaining.contains(cell));
+
+                    match next {
+                        None => break,
+                        Some(cell) => {
+                            remaining.remove(&cell);
+                            if forward {
+                                chain.push_back(cell);
+                            } else {
+                                chain.push_front(cell);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if chain.len() >= MIN_CONTOUR_CELLS {
+                let points = chain
+                    .into_iter()
+                    .map(|(x, y)| Point {
+                        x: (x as f32 + 0.5) * QUADTREE_DIVISOR,
+                        y: (y as f32 + 0.5) * QUADTREE_DIVISOR,
+                    })
+                    .collect();
+                contours.push(Polyline::new(points).smoothed(2));
+            }
+        }
+
+        contours.sort_by(|a, b| b.length().total_cmp(&a.length()));
+        contours
+    }
+
+    /**
+        Places the word with its baseline on one of the contours, rotated along the contour
+        tangent. The word shrinks, if it doesn't fit anywhere.
+    */
+    fn add_word_on_contour(&self, word: Word<'a>, contours: &[Polyline], cursors: &mut [f32]) {
+        let mut scale = word.scale;
+
+        while scale >= 10. {
+            let length = match word.rebuild(scale, Point::default(), Rotation::Zero) {
+                Ok(w) => w.normalized_bbox().width(),
+                Err(_) => return,
+            };
+            let step = f32::max(length / 4., QUADTREE_DIVISOR);
+
+            for (contour, cursor) in contours.iter().zip(cursors.iter_mut()) {
+                let mut position = *cursor;
+                while position + length <= contour.length() {
+                    let (start, end) = match (
+                        contour.point_at(position),
+                        contour.point_at(position + length),
+                    ) {
+                        (Some(start), Some(end)) => (start, end),
+                        _ => break,
+                    };
+                    let angle = (end.y - start.y).atan2(end.x - start.x).to_degrees();
+
+                    // keep the text readable by running it the other way along the contour
+                    let (origin, angle) = if angle > 90. {
+                        (end, angle - 180.)
+                    } else if angle < -90. {
+                        (end, angle + 180.)
+                    } else {
+                        (start, angle)
+                    };
+
+                    if let Ok(mut w) =
+                        word.rebuild(scale, Point::default(), Rotation::Custom(angle))
+                    {
+                        w.move_word(&origin);
+                        if self.converted_dimensions().contains(&w.bounding_box)
+                            && self.try_insert(w, false).is_ok()
+                        {
+                            *cursor = position + length + CONTOUR_WORD_GAP;
+                            return;
+                        }
+                    }
+
+                    position += step;
+                }
+            }
+
+            scale -= 5.;
+        }
+    }
+
+    pub(crate) fn put_text_outline(&self, inp: Vec<Word<'a>>) {
+        let contours = self.contour_polylines();
+        let mut cursors = vec![0.; contours.len()];
+
+        for word in inp {
+            self.add_word_on_contour(word, &contours, &mut cursors);
+        }
+    }
+
     pub(crate) fn put_text_sync(&self, inp: Vec<Word<'a>>) {
         for word in inp {
             self.add_word(word);
@@ -315,31 +605,81 @@ impl<'a> WordCloud<'a> {
 
     /**
         Add new words to the [`WordCloud`]. For the best results, call this function only once.
+
+        With [`WordContent::Regions`], the words of each region are only placed inside that
+        region of the [`RegionMask`] given to the [`WordCloudBuilder`], at most
+        `max_word_count` per region. Fails without writing any words, if a region doesn't
+        exist in the mask.
+    */
+    pub fn write_content(
+        &self,
+        content: impl Into<WordContent>,
+        max_word_count: usize,
+    ) -> Result<(), String> {
+        match content.into() {
+            WordContent::Words(words) => self.write_words(words, max_word_count, None),
+            WordContent::Regions(regions) => {
+                if let Some((id, _)) = regions.iter().find(|(id, _)| !self.has_region(*id)) {
+                    return Err(format!("Region {} is missing from the RegionMask!", id));
+                }
+                for (id, words) in regions {
+                    self.write_words(words, max_word_count, Some(id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+        Whether the [`RegionMask`] has at least one cell of the region `id`
     */
-    pub fn write_content(&self, content: RankedWords, max_word_count: usize) {
+    fn has_region(&self, id: usize) -> bool {
+        #[cfg(feature = "background_image")]
+        if let Some(map) = &self.regions {
+            return map.bounds(id).is_some();
+        }
+        let _ = id;
+        false
+    }
+
+    fn write_words(&self, content: RankedWords, max_word_count: usize, region: Option<usize>) {
+        if content.0.is_empty() {
+            return;
+        }
         let max = content.0.iter().max_by_key(|x| x.count()).unwrap().count() as f32;
+        let last_rank = content.0.len().min(max_word_count).saturating_sub(1).max(1) as f32;
 
         let inp: Vec<WordBuilder> = content
             .0
             .iter()
             .take(max_word_count)
-            .flat_map(|w| {
+            .enumerate()
+            .flat_map(|(rank, w)| {
                 let font_size_range = Word::guess_font_size_range(w.content(), &self.dimensions);
-                let ws = w.content().guess_script();
-                let used_font = match self.font.get_font_for_script(&ws) {
-                    None => {
-                        return None;
-                    }
-                    Some(f) => f,
+                let info = FontSelectionInfo {
+                    text: w.content(),
+                    count: w.count(),
+                    rank,
+                    weight: w.count() as f32 / max,
+                    region,
                 };
+                let primary = self.font.select_font(&info, &self.font_selection);
+                let runs = self.font.font_runs(w.content(), primary)?;
 
                 let scale = ((w.count() as f32).log2() / max.log2()) * font_size_range.end;
                 Some(
                     WordBuilder::new()
                         .content(w.content().to_string())
                         .scale(scale)
-                        .font(used_font)
-                        .start(Point::default()),
+                        .runs(runs)
+                        .start(Point::default())
+                        .region(region)
+                        .count(w.count())
+                        .rank(rank)
+                        .emphasis(Emphasis {
+                            weight: w.count() as f32 / max,
+                            rank: 1. - rank as f32 / last_rank,
+                        }),
                 )
             })
             .collect();
@@ -362,7 +702,7 @@ impl<'a> WordCloud<'a> {
                 }
             })
             .map(|mut w| {
-                let (x_range, y_range) = w.get_positioning_range(&self.dimensions);
+                let (x_range, y_range) = self.positioning_range(&w);
 
                 let point = (
                     thread_rng().gen_range(x_range),
@@ -377,6 +717,11 @@ impl<'a> WordCloud<'a> {
         words.sort_by_key(|d| d.scale as u64);
         words.reverse();
 
+        if self.layout == Layout::Outline {
+            self.put_text_outline(words);
+            return;
+        }
+
         let em: &[Word] = &[];
         let (first, second) = if words.len() > 20 {
             words.split_at(20)
@@ -412,6 +757,157 @@ impl<'a> WordCloud<'a> {
         }
     }
 
+    /**
+        Fill color of the word. Colors assigned to a region take precedence over the
+        [`ColorScheme`], which takes precedence over colors sampled from the background image.
+    */
+    fn color_for_word(&self, word: &Word, rank_count: usize) -> Option<Color> {
+        #[cfg(feature = "background_image")]
+        if let (Some(map), Some(region)) = (&self.regions, word.region) {
+            if let Some(fill) = map.fill(region) {
+                return Some((*fill).into());
+            }
+        }
+
+        if let Some(scheme) = &self.color_scheme {
+            return Some(scheme.color(&word.text, word.rank, rank_count, word.emphasis.weight));
+        }
+
+        #[cfg(feature = "background_image")]
+        if self.bg_image.is_some() {
+            return Some(self.get_color_for_word(word).into());
+        }
+
+        None
+    }
+
+    /**
+        Presentation attributes of the word, as returned by the style hook.
+    */
+    pub(crate) fn style_for_word(&self, word: &Word, rank_count: usize) -> WordStyle {
+        let color = self.color_for_word(word, rank_count);
+        match &self.style {
+            None => WordStyle {
+                fill: color,
+                ..Default::default()
+            },
+            Some(style) => {
+                let mut s = style(&PlacedWordInfo::new(word, color));
+                if s.fill.is_none() {
+                    s.fill = color;
+                }
+                s
+            }
+        }
+    }
+
+    /**
+        All placed words ordered by rank, so that the exports don't depend on the order in which
+        the words were placed.
+    */
+    pub(crate) fn sorted_words<'t>(tree: &'t Quadtree<u64, Word<'a>>) -> Vec<&'t Word<'a>> {
+        let mut words: Vec<&Word> = tree.iter().map(|x| x.value_ref()).collect();
+        words.sort_by(|a, b| {
+            (a.rank, a.region, &a.text)
+                .cmp(&(b.rank, b.region, &b.text))
+                .then(a.offset.x.total_cmp(&b.offset.x))
+                .then(a.offset.y.total_cmp(&b.offset.y))
+        });
+        words
+    }
+
+    pub(crate) fn rank_count(words: &[&Word]) -> usize {
+        words.iter().map(|w| w.rank + 1).max().unwrap_or(0)
+    }
+
+    /**
+        The area of the exports, which are the [`crate::Dimensions`] extended by the margin
+    */
+    pub(crate) fn canvas(&self, options: &ExportOptions) -> Rect<f32> {
+        let margin = options.margin;
+        Rect {
+            min: Point {
+                x: -margin,
+                y: -margin,
+            },
+            max: Point {
+                x: self.dimensions.width() as f32 + margin,
+                y: self.dimensions.height() as f32 + margin,
+            },
+        }
+    }
+
+    /**
+        The root element showing `area`, which is labelled by the title, description and, if
+        `word_list` is written, the list of words.
+    */
+    pub(crate) fn svg_document(
+        &self,
+        options: &ExportOptions,
+        area: Rect<f32>,
+        word_list: bool,
+    ) -> Document {
+        let (width, height) = (area.width(), area.height());
+
+        let labels = [
+            (options.title.is_some(), TITLE_ID),
+            (options.description.is_some(), DESCRIPTION_ID),
+            (word_list, WORD_LIST_ID),
+        ]
+        .into_iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, id)| id)
+        .join(" ");
+
+        let mut document = Document::new()
+            .set(
+                "viewBox",
+                format!(
+                    "{} {} {} {}",
+                    options.number(area.min.x),
+                    options.number(area.min.y),
+                    options.number(width),
+                    options.number(height)
+                ),
+            )
+            .set("height", options.number(height))
+            .set("width", options.number(width));
+        if !labels.is_empty() {
+            document.assign("role", "img");
+            document.assign("aria-labelledby", labels);
+        }
+        document
+    }
+
+    pub(crate) fn write_metadata<W: Write>(
+        writer: &mut SvgWriter<W>,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        if let Some(title) = &options.title {
+            let title = Title::new()
+                .set("id", TITLE_ID)
+                .add(svg::node::Text::new(escape_xml(title)));
+            writer.node(&title)?;
+        }
+        if let Some(description) = &options.description {
+            let description = Description::new()
+                .set("id", DESCRIPTION_ID)
+                .add(svg::node::Text::new(escape_xml(description)));
+            writer.node(&description)?;
+        }
+        Ok(())
+    }
+
+    fn write_background<W: Write>(
+        &self,
+        writer: &mut SvgWriter<W>,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        options
+            .background
+            .write_to(writer, self.canvas(options), options)
+    }
+
     /**
         Export the resulting WordCloud as an SVG formatted [`String`]. Here the text is rendered using SVG Paths instead
         of Text elements. This leads to way bigger file sizes, but also to a little bit more accurate
@@ -421,56 +917,145 @@ impl<'a> WordCloud<'a> {
         function.
     */
     pub fn export_rendered(&self) -> Result<String, Error> {
+        self.export_rendered_with(&RenderedOptions::default())
+    }
+
+    /**
+        Like [`Self::export_rendered`], but customized with [`RenderedOptions`].
+    */
+    pub fn export_rendered_with(&self, options: &RenderedOptions) -> Result<String, Error> {
+        let mut target = Vec::new();
+        self.write_rendered_to(&mut target, options)?;
+
+        Ok(String::from_utf8(target).expect("decoding the written string failed"))
+    }
+
+    /**
+        Streams the result of [`Self::export_rendered_with`] into `target`, without holding the
+        whole document in memory. The paths are built in parallel, a small batch at a time. With
+        [`RenderedOptions::merge_paths`], only the merged path data is held until the end.
+    */
+    pub fn write_rendered_to<W: Write>(
+        &self,
+        target: W,
+        options: &RenderedOptions,
+    ) -> Result<(), Error> {
+        self.write_rendered_area(target, options, self.canvas(&options.export_options))
+    }
+
+    /**
+        Writes the SVG with paths showing only `area`. Words outside of it are left out, words
+        crossing its border are clipped by the view box.
+    */
+    pub(crate) fn write_rendered_area<W: Write>(
+        &self,
+        target: W,
+        rendered_options: &RenderedOptions,
+        area: Rect<f32>,
+    ) -> Result<(), Error> {
+        let options = &rendered_options.export_options;
         let ct = self.ct.read();
-        let collected_entries: Vec<&Word> = ct.iter().map(|x| x.value_ref()).collect();
+        let sorted = WordCloud::sorted_words(&ct);
+        let rank_count = WordCloud::rank_count(&sorted);
+        // the position in rank order is kept, so that ids are the same in every area
+        let collected_entries = sorted
+            .into_iter()
+            .enumerate()
+            .filter(|(_, word)| word.bounding_box.overlaps(&area))
+            .collect::<Vec<(usize, &Word)>>();
+
+        let mut writer = SvgWriter::new(target, options.formatting);
+        writer.start(&self.svg_document(options, area, rendered_options.word_list))?;
+        WordCloud::write_metadata(&mut writer, options)?;
+        self.write_background(&mut writer, options)?;
+        if let Some(css) = &options.style_sheet {
+            writer.node(&Style::new(css.as_str()))?;
+        }
 
-        let sliced = collected_entries.par_iter().chunks(
-            (collected_entries.len() as f64 / available_parallelism!() as f64).ceil() as usize,
-        );
+        if rendered_options.word_list {
+            // invisible, but read by screen readers as part of the label of the document
+            let words = collected_entries
+                .iter()
+                .map(|(_, w)| w.text.as_str())
+                .join(", ");
+            let list = Text::new()
+                .set("id", WORD_LIST_ID)
+                .set("x", 0)
+                .set("y", 0)
+                .set("font-size", 1)
+                .set("fill-opacity", 0)
+                .add(svg::node::Text::new(escape_xml(&words)));
+            writer.node(&list)?;
+        }
 
-        let doc_mutex = Arc::new(Mutex::new(
-            Document::new()
-                .set(
-                    "viewBox",
-                    (0, 0, self.dimensions.width(), self.dimensions.height()),
-                )
-                .set("height", self.dimensions.height())
-                .set("width", self.dimensions.width()),
-        ));
-
-        sliced.for_each(|x| {
-            for word in x {
-                let mut p = Path::new().set("d", word.d()).set("stoke", "none");
-                #[cfg(feature = "background_image")]
-                {
-                    let color = self.get_color_for_word(word);
-                    p.assign("fill", color_to_rgb_string(&color));
+        // words with the same style are merged into one path in the order of their first word
+        let merge_paths = rendered_options.merge_paths && !options.has_word_attributes();
+        let mut merged: Vec<(WordStyle, String, Vec<&str>)> = Vec::new();
+
+        for batch in collected_entries.chunks(available_parallelism!() * RENDER_BATCH_SIZE) {
+            let paths = batch
+                .par_iter()
+                .map(|(_, word)| {
+                    (
+                        self.style_for_word(word, rank_count),
+                        word.d(options.precision),
+                    )
+                })
+                .collect::<Vec<(WordStyle, String)>>();
+
+            for ((index, word), (style, d)) in batch.iter().zip(paths) {
+                if !merge_paths {
+                    let mut p = WordCloud::styled_path(&style, d, &[&word.text], rendered_options);
+                    let info = PlacedWordInfo::new(word, self.color_for_word(word, rank_count));
+                    options.apply_word_attributes(&mut p, *index, &info, style.class.as_deref());
+                    writer.node(&p)?;
+                    continue;
                 }
 
-                let _s = p.to_string();
-                {
-                    doc_mutex.lock().append(p);
+                match merged.iter_mut().find(|(s, _, _)| *s == style) {
+                    Some((_, merged_d, labels)) => {
+                        merged_d.push_str(&d);
+                        labels.push(&word.text);
+                    }
+                    None => merged.push((style, d, vec![&word.text])),
                 }
             }
-        });
+        }
 
-        let lock = doc_mutex.lock();
-        let mut target = Cursor::new(Vec::new());
-        match svg::write(&mut target, &lock.clone()) {
-            Ok(_) => {}
-            Err(e) => return Err(e),
-        };
+        for (style, d, labels) in merged {
+            writer.node(&WordCloud::styled_path(
+                &style,
+                d,
+                &labels,
+                rendered_options,
+            ))?;
+        }
 
-        Ok(String::from_utf8(target.into_inner()).expect("decoding the written string failed"))
+        writer.finish()?;
+        Ok(())
+    }
+
+    fn styled_path(
+        style: &WordStyle,
+        d: String,
+        labels: &[&str],
+        options: &RenderedOptions,
+    ) -> Path {
+        let mut p = Path::new().set("d", d).set("stoke", "none");
+        style.apply(&mut p);
+        if options.word_labels {
+            p.assign("role", "img");
+            p.assign("aria-label", escape_xml(&labels.join(" ")));
+        }
+        p
     }
 
     /**
         Writes the result of [`Self::export_rendered`] to a file.
     */
     pub fn export_rendered_to_file(&self, filename: &str) -> Result<(), Error> {
-        let string = self.export_rendered()?;
-        std::fs::write(filename, string.as_bytes())?;
-        Ok(())
+        let file = BufWriter::new(File::create(filename)?);
+        self.write_rendered_to(file, &RenderedOptions::default())
     }
 
     /**
@@ -481,80 +1066,233 @@ impl<'a> WordCloud<'a> {
        most use-cases.
     */
     pub fn export_text(&self) -> Result<String, Error> {
-        let mut document = Document::new()
-            .set(
-                "viewBox",
-                (0, 0, self.dimensions.width(), self.dimensions.height()),
-            )
-            .set("height", self.dimensions.height())
-            .set("width", self.dimensions.width());
+        self.export_text_with(&ExportOptions::default())
+    }
+
+    /**
+        Like [`Self::export_text`], but customized with [`ExportOptions`].
+    */
+    pub fn export_text_with(&self, options: &ExportOptions) -> Result<String, Error> {
+        let mut target = Vec::new();
+        self.write_text_to(&mut target, options)?;
+
+        Ok(String::from_utf8(target).expect("decoding the written string failed"))
+    }
+
+    /**
+        Streams the result of [`Self::export_text_with`] into `target`, without holding the
+        whole document in memory.
+    */
+    pub fn write_text_to<W: Write>(&self, target: W, options: &ExportOptions) -> Result<(), Error> {
+        let mut writer = SvgWriter::new(target, options.formatting);
+        self.write_text_svg(&mut writer, options, |_, t| Box::new(t))?;
+
+        writer.finish()?;
+        Ok(())
+    }
+
+    /**
+        Writes the SVG document with text elements. Each word passes through `word_node`, which
+        may decorate the text element or wrap it into other elements.
+    */
+    pub(crate) fn write_text_svg<W: Write>(
+        &self,
+        writer: &mut SvgWriter<W>,
+        options: &ExportOptions,
+        word_node: impl Fn(&PlacedWordInfo, Text) -> Box<dyn Node>,
+    ) -> Result<(), Error> {
+        writer.start(&self.svg_document(options, self.canvas(options), false))?;
+        WordCloud::write_metadata(writer, options)?;
+        self.write_background(writer, options)?;
 
         let read_lock = self.ct.read();
-        for (font, group) in &read_lock
-            .iter()
-            .map(|y| y.value_ref())
-            .group_by(|k| k.used_font)
-        {
-            let dt = match font.packed() {
-                None => font.reference().data,
-                Some(s) => s.as_slice(),
-            };
-            let enc = STANDARD_NO_PAD.encode(dt);
-            document.append(Style::new(format!(
-                "@font-face{{font-family:\"{}\";src:url(\"data:{};charset=utf-8;base64,{}\");}}",
-                font.name(),
-                font.font_type().embed_tag(),
-                enc
-            )));
+        let collected_entries = WordCloud::sorted_words(&read_lock);
+        let rank_count = WordCloud::rank_count(&collected_entries);
+
+        if options.embed_fonts {
+            let mut fonts: Vec<(&Font, Vec<&Word>)> = Vec::new();
+            for word in &collected_entries {
+                for run in &word.runs {
+                    match fonts.iter_mut().find(|(f, _)| *f == run.font) {
+                        None => fonts.push((run.font, vec![word])),
+                        Some((_, words)) => {
+                            if !words.last().is_some_and(|w| std::ptr::eq(*w, *word)) {
+                                words.push(word)
+                            }
+                        }
+                    }
+                }
+            }
 
-            let mut gr = Group::new().set("font-family", font.name());
+            let mut font_faces = String::new();
+            for (font, words) in fonts {
+                let (dt, embed_tag) = embedded_font(font, &words, options);
+                let enc = STANDARD_NO_PAD.encode(dt);
+                font_faces.push_str(&format!(
+                    "@font-face{{font-family:\"{}\";src:url(\"data:{};charset=utf-8;base64,{}\");}}",
+                    font.name(),
+                    embed_tag,
+                    enc
+                ));
+            }
+            writer.node(&Style::new(font_faces))?;
+        }
+        if let Some(css) = &options.style_sheet {
+            writer.node(&Style::new(css.as_str()))?;
+        }
+
+        // consecutive words of the same font share a group, without changing the order. The
+        // group is labelled with its words, in the order they are read.
+        let mut index = 0;
+        for (font, group) in &collected_entries.into_iter().group_by(|k| k.used_font) {
+            let group = group.collect::<Vec<&Word>>();
+            let label = group.iter().map(|w| w.text.as_str()).join(", ");
+            writer.start(
+                &Group::new()
+                    .set("font-family", font.name())
+                    .set("role", "group")
+                    .set("aria-label", escape_xml(&label)),
+            )?;
 
             for word in group {
+                let (x, y) = (options.number(word.offset.x), options.number(word.offset.y));
                 let mut t = Text::new()
-                    .set("x", word.offset.x)
-                    .set("y", word.offset.y)
-                    .set("font-size", word.scale);
-
-                #[cfg(feature = "background_image")]
-                {
-                    let color = self.get_color_for_word(word);
-                    t.assign("fill", color_to_rgb_string(&color));
+                    .set("x", x.as_str())
+                    .set("y", y.as_str())
+                    .set("font-size", options.number(word.scale));
+                if word.rtl {
+                    // in right-to-left text the end is on the left, where the path export starts
+                    t.assign("direction", "rtl");
+                    t.assign("unicode-bidi", "embed");
+                    t.assign("text-anchor", "end");
                 }
 
-                match word.rotation {
-                    Rotation::Zero => (),
-                    Rotation::Ninety | Rotation::OneEighty | Rotation::TwoSeventy => {
-                        t.assign(
-                            "style",
-                            format!(
-                                "transform: rotate({}deg); transform-origin: {}px {}px",
-                                word.rotation.inner(),
-                                word.offset.x,
-                                word.offset.y
-                            ),
-                        );
-                    }
+                let style = self.style_for_word(word, rank_count);
+                style.apply(&mut t);
+
+                WordCloud::rotate_text(&mut t, word, options);
+                if let Some(settings) = WordCloud::variation_settings(word.used_font, word) {
+                    let style = match t.get_attributes().get("style") {
+                        Some(style) => format!("{}; font-variation-settings: {}", style, settings),
+                        None => format!("font-variation-settings: {}", settings),
+                    };
+                    t.assign("style", style);
                 }
-                t.append(svg::node::Text::new(&word.text));
-                gr.append(t);
+                t.append(svg::node::Text::new(WordCloud::text_content(word)));
+
+                let info = PlacedWordInfo::new(word, self.color_for_word(word, rank_count));
+                options.apply_word_attributes(&mut t, index, &info, style.class.as_deref());
+                index += 1;
+
+                writer.node(&word_node(&info, t))?;
             }
 
-            document.append(gr);
+            writer.end()?;
         }
 
-        let mut cursor = Cursor::new(Vec::new());
-        svg::write(&mut cursor, &document)?;
+        writer.end()
+    }
+
+    /**
+        The escaped text of `word`. Runs in other fonts than the one of the word are written as
+        `tspan`s, without whitespace between them.
+    */
+    fn text_content(word: &Word) -> String {
+        let mut runs = word.runs.iter().collect::<Vec<_>>();
+        runs.sort_by_key(|run| run.text.start);
+
+        let mut content = String::new();
+        for run in runs {
+            let text = escape_xml(&word.text[run.text.clone()]);
+            if run.font == word.used_font {
+                content.push_str(&text);
+            } else {
+                let style = match WordCloud::variation_settings(run.font, word) {
+                    Some(settings) => format!(" style=\"font-variation-settings: {}\"", settings),
+                    None => String::new(),
+                };
+                content.push_str(&format!(
+                    "<tspan font-family=\"{}\"{}>{}</tspan>",
+                    escape_xml(run.font.name()),
+                    style,
+                    text
+                ));
+            }
+        }
+        content
+    }
+
+    /**
+        Value of `font-variation-settings` for the text of `word` in `font`, if the font has
+        variation axes
+    */
+    fn variation_settings(font: &Font, word: &Word) -> Option<String> {
+        let variations = font.variations(word.emphasis.weight, word.emphasis.rank);
+        if variations.is_empty() {
+            return None;
+        }
+        Some(
+            variations
+                .iter()
+                .map(|(tag, value)| {
+                    format!(
+                        "\"{}\" {}",
+                        String::from_utf8_lossy(&tag.to_be_bytes()),
+                        format_number(*value, 2)
+                    )
+                })
+                .join(", "),
+        )
+    }
+
+    /**
+        Rotates the text element of `word` around its origin, with a CSS transform or, for
+        [`ExportOptions::compatible`], with the `transform` attribute. Vertical words are set
+        with upright glyphs in the `vertical-rl` writing mode instead, using the SVG 1.1
+        attributes for [`ExportOptions::compatible`].
+    */
+    fn rotate_text(t: &mut Text, word: &Word, options: &ExportOptions) {
+        match word.rotation {
+            Rotation::Zero => return,
+            Rotation::Vertical if options.compatible => {
+                t.assign("writing-mode", "tb-rl");
+                t.assign("glyph-orientation-vertical", "0");
+                return;
+            }
+            Rotation::Vertical => {
+                t.assign(
+                    "style",
+                    "writing-mode: vertical-rl; text-orientation: upright",
+                );
+                return;
+            }
+            _ => {}
+        }
 
-        Ok(String::from_utf8_lossy(&cursor.into_inner()).into())
+        let (angle, x, y) = (
+            options.number(word.rotation.inner()),
+            options.number(word.offset.x),
+            options.number(word.offset.y),
+        );
+        if options.compatible {
+            t.assign("transform", format!("rotate({} {} {})", angle, x, y));
+        } else {
+            t.assign(
+                "style",
+                format!(
+                    "transform: rotate({}deg); transform-origin: {}px {}px",
+                    angle, x, y
+                ),
+            );
+        }
     }
 
     /**
     Writes the result of [`Self::export_text`] to a file.
      */
     pub fn export_text_to_file(&self, filename: &str) -> Result<(), Error> {
-        let string = self.export_text()?;
-        std::fs::write(filename, string.as_bytes())?;
-        Ok(())
+        let file = BufWriter::new(File::create(filename)?);
+        self.write_text_to(file, &ExportOptions::default())
     }
 }
 
@@ -614,7 +1352,7 @@ impl<'a> WordCloud<'a> {
 
         for word in self.ct.read().iter() {
             let p = Path::new()
-                .set("d", word.value_ref().d())
+                .set("d", word.value_ref().d(2))
                 .set("stoke", "none")
                 .set("fill", "gray");
             document.append(p);
@@ -703,6 +1441,13 @@ pub struct WordCloudBuilder<'a> {
     dimensions: Option<Dimensions>,
     font: Option<&'a FontSet<'a>>,
     image: Option<&'a DynamicImage>,
+    font_selection: FontSelection<'a>,
+    layout: Layout,
+    vertical_writing: bool,
+    #[cfg(feature = "background_image")]
+    regions: Option<RegionMask<'a>>,
+    color_scheme: Option<ColorScheme>,
+    style: Option<StyleFn<'a>>,
 }
 
 impl<'a> WordCloudBuilder<'a> {
@@ -726,6 +1471,15 @@ impl<'a> WordCloudBuilder<'a> {
         self
     }
 
+    /**
+    Optional: [`FontSelection`], which decides the font of every word based on its rank and
+    weight, defaults to [`FontSelection::FirstMatch`]
+     */
+    pub fn font_selection(mut self, font_selection: FontSelection<'a>) -> Self {
+        self.font_selection = font_selection;
+        self
+    }
+
     /**
     Optional: Image, which is used for border detection
      */
@@ -734,6 +1488,54 @@ impl<'a> WordCloudBuilder<'a> {
         self
     }
 
+    /**
+    Optional: [`RegionMask`], which splits the canvas into regions, each filled with its own
+    words, see [`WordContent::Regions`]
+     */
+    #[cfg(feature = "background_image")]
+    pub fn regions(mut self, regions: RegionMask<'a>) -> Self {
+        self.regions = Some(regions);
+        self
+    }
+
+    /**
+    Optional: [`ColorScheme`] used to color the words. Takes precedence over the colors of the
+    background image.
+     */
+    pub fn color_scheme(mut self, color_scheme: ColorScheme) -> Self {
+        self.color_scheme = Some(color_scheme);
+        self
+    }
+
+    /**
+    Optional: Hook, which decides the [`WordStyle`] of every word at export time. Works for
+    both [`WordCloud::export_text`] and [`WordCloud::export_rendered`].
+     */
+    pub fn style(
+        mut self,
+        style: impl Fn(&PlacedWordInfo) -> WordStyle + Send + Sync + 'a,
+    ) -> Self {
+        self.style = Some(Box::new(style));
+        self
+    }
+
+    /**
+    Optional: [`Layout`] used to arrange the words, defaults to [`Layout::Fill`]
+     */
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /**
+    Optional: Chinese, Japanese and Korean words, which would be rotated by 90° or 270°, are set
+    vertically instead, with upright glyphs stacked from top to bottom. Defaults to `false`.
+     */
+    pub fn vertical_writing(mut self, vertical_writing: bool) -> Self {
+        self.vertical_writing = vertical_writing;
+        self
+    }
+
     /**
     Build the [`WordCloud`], basically free, no calculations are done here
      */
@@ -744,11 +1546,426 @@ impl<'a> WordCloudBuilder<'a> {
             (None, _) => return Err("Missing Dimensions in WordCloudBuilder!".into()),
         };
 
+        if self.layout == Layout::Outline && self.image.is_none() {
+            return Err("Outline Layout requires an Image in WordCloudBuilder!".into());
+        }
+        wc.font_selection = self.font_selection;
+        wc.layout = self.layout;
+        wc.vertical_writing = self.vertical_writing;
+        wc.color_scheme = self.color_scheme;
+        wc.style = self.style;
+
         #[cfg(feature = "background_image")]
         if let Some(i) = self.image {
             wc.add_background(i);
         }
 
+        #[cfg(feature = "background_image")]
+        if let Some(r) = self.regions {
+            wc.regions = Some(r.to_map(
+                (wc.dimensions.width() as f32 / QUADTREE_DIVISOR) as u32,
+                (wc.dimensions.height() as f32 / QUADTREE_DIVISOR) as u32,
+            ));
+        }
+
         Ok(wc)
     }
 }
+
+/**
+    Whether `text` is written in a script, which is traditionally set vertically
+*/
+fn is_vertical_script(text: &str) -> bool {
+    use unicode_script::{Script, UnicodeScript};
+
+    let mut scripts = text
+        .chars()
+        .map(|c| c.script())
+        .filter(|s| !matches!(s, Script::Common | Script::Inherited))
+        .peekable();
+    scripts.peek().is_some()
+        && scripts.all(|s| {
+            matches!(
+                s,
+                Script::Han
+                    | Script::Hiragana
+                    | Script::Katakana
+                    | Script::Hangul
+                    | Script::Bopomofo
+            )
+        })
+}
+
+#[test]
+fn test_compatible_rotation() {
+    let mut font_data =
+        Vec::from(include_bytes!("../../example/assets/OpenSans-Regular.ttf") as &[u8]);
+    let font = Font::from_data(&mut font_data).unwrap();
+    let options = ExportOptions::new().compatible(true);
+    let origin = Point { x: 150., y: 200. };
+
+    let runs = [(0..6, &font)];
+    let upright = Word::build(
+        "Rotate",
+        &runs,
+        40.,
+        origin,
+        Rotation::Zero,
+        Emphasis::default(),
+    )
+    .unwrap();
+    for rotation in [
+        Rotation::Ninety,
+        Rotation::TwoSeventy,
+        Rotation::Custom(30.),
+    ] {
+        let word =
+            Word::build("Rotate", &runs, 40., origin, rotation, Emphasis::default()).unwrap();
+        let mut t = Text::new();
+        WordCloud::rotate_text(&mut t, &word, &options);
+
+        // rotate(angle cx cy), applied to the upright glyphs, has to match the rotated glyphs
+        let transform = t.get_attributes()["transform"].to_string();
+        let values: Vec<f32> = transform
+            .trim_start_matches("rotate(")
+            .trim_end_matches(')')
+            .split(' ')
+            .map(|v| v.parse().unwrap())
+            .collect();
+        let (sin, cos) = values[0].to_radians().sin_cos();
+        let center = Point {
+            x: values[1],
+            y: values[2],
+        };
+
+        let expected = word.polygons(0.1);
+        let transformed = upright.polygons(0.1);
+        assert_eq!(expected.len(), transformed.len());
+        for (e, u) in expected.iter().flatten().zip(transformed.iter().flatten()) {
+            let d = *u - center;
+            let p = Point {
+                x: center.x + d.x * cos - d.y * sin,
+                y: center.y + d.x * sin + d.y * cos,
+            };
+            assert!(
+                p.distance(e) < 0.05,
+                "{:?} != {:?} for {:?}",
+                p,
+                e,
+                rotation
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_font_set() -> FontSet<'static> {
+    let data = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    crate::common::font::FontSetBuilder::new()
+        .push(Font::from_collection(data, 0).unwrap())
+        .build()
+        .unwrap()
+}
+
+#[cfg(test)]
+pub(crate) fn test_words(words: &[(&str, usize)]) -> RankedWords {
+    RankedWords::rank(
+        words
+            .iter()
+            .flat_map(|(w, count)| std::iter::repeat_n(w.to_string(), *count))
+            .collect(),
+    )
+}
+
+#[cfg(feature = "background_image")]
+#[test]
+fn test_outline_layout() {
+    // a dark square in the middle of the canvas, from 240 to 560 after scaling
+    let image = DynamicImage::ImageLuma8(image::GrayImage::from_fn(200, 200, |x, y| {
+        let inside = (60..140).contains(&x) && (60..140).contains(&y);
+        image::Luma([if inside { 0 } else { 255 }])
+    }));
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .image(&image)
+        .layout(Layout::Outline)
+        .build()
+        .unwrap();
+
+    let contours = wc.contour_polylines();
+    assert!(!contours.is_empty());
+    // one contour per edge of the square
+    assert_eq!(contours.len(), 4);
+    assert!(contours.iter().all(|c| c.length() > 250.));
+
+    wc.write_content(
+        test_words(&[("outline", 16), ("contour", 8), ("edge", 4)]),
+        10,
+    )
+    .unwrap();
+    let tree = wc.ct.read();
+    assert!(tree.iter().count() > 0);
+
+    // the baseline of every word starts on the edge of the square
+    for entry in tree.iter() {
+        let origin = entry.value_ref().offset;
+        let to_edge = [
+            origin.x - 240.,
+            origin.x - 560.,
+            origin.y - 240.,
+            origin.y - 560.,
+        ]
+        .iter()
+        .map(|d| d.abs())
+        .fold(f32::MAX, f32::min);
+        let inside_band = (224. ..576.).contains(&origin.x) && (224. ..576.).contains(&origin.y);
+        assert!(
+            inside_band && to_edge < 16.,
+            "{} at {:?}",
+            entry.value_ref().text,
+            origin
+        );
+        assert!(matches!(entry.value_ref().rotation, Rotation::Custom(_)));
+    }
+}
+
+#[cfg(feature = "background_image")]
+#[test]
+fn test_region_content() {
+    use image::{Rgba, RgbaImage};
+
+    // the mask is wider than the canvas, so it only covers the upper half after resizing
+    let mask = DynamicImage::ImageRgba8(RgbaImage::from_fn(100, 50, |x, _| {
+        if x < 50 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    }));
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .regions(
+            RegionMask::new(&mask)
+                .region(0, Rgba([255, 0, 0, 255]))
+                .region(1, Rgba([0, 0, 255, 255]))
+                .region(2, Rgba([0, 255, 0, 255])),
+        )
+        .build()
+        .unwrap();
+
+    let pros = test_words(&[("fast", 16), ("safe", 8), ("fun", 4)]);
+    let cons = test_words(&[("slow", 16), ("hard", 8), ("odd", 4)]);
+    // region 2 has no pixels in the mask
+    let lost = vec![
+        (0, test_words(&[("lost", 4)])),
+        (2, test_words(&[("lost", 4)])),
+    ];
+    assert!(wc.write_content(lost, 10).is_err());
+    assert_eq!(wc.ct.read().iter().count(), 0);
+
+    wc.write_content(vec![(0, pros), (1, cons)], 10).unwrap();
+    let tree = wc.ct.read();
+    assert!(tree.iter().count() > 0);
+    for entry in tree.iter() {
+        let word = entry.value_ref();
+        let bbox = &word.bounding_box;
+        assert!(bbox.max.y <= 400., "{} at {:?}", word.text, bbox);
+        match word.region {
+            Some(0) => assert!(bbox.max.x <= 400., "{} at {:?}", word.text, bbox),
+            Some(1) => assert!(bbox.min.x >= 400., "{} at {:?}", word.text, bbox),
+            _ => panic!("{} has no region", word.text),
+        }
+    }
+}
+
+#[test]
+fn test_style_hook() {
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .style(|info| WordStyle {
+            fill: Some(if info.rank == 0 {
+                Color::rgb(200, 0, 0)
+            } else {
+                Color::rgb(90, 90, 90)
+            }),
+            opacity: Some(if info.rank == 0 { 1. } else { 0.5 }),
+            class: Some(match info.text {
+                "quoted" => String::from(r#"word-quoted" onclick="alert(1)"#),
+                text => format!("word-{}", text),
+            }),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    wc.write_content(
+        test_words(&[("styled", 16), ("plain", 8), ("quoted", 4)]),
+        10,
+    )
+    .unwrap();
+    assert_eq!(wc.ct.read().iter().count(), 3);
+
+    for svg in [wc.export_text().unwrap(), wc.export_rendered().unwrap()] {
+        let styled = svg.find(r#"class="word-styled""#).unwrap();
+        let plain = svg.find(r#"class="word-plain""#).unwrap();
+        assert!(styled < plain);
+        assert!(svg.contains(r#"fill="rgb(200, 0, 0)""#));
+        assert!(svg.contains(r#"fill="rgb(90, 90, 90)""#));
+        assert!(svg.contains(r#"opacity="0.5""#));
+        // the class of the style is escaped like every other attribute
+        assert!(svg.contains(r#"class="word-quoted&quot; onclick=&quot;alert(1)""#));
+        assert!(!svg.contains("onclick=\""));
+    }
+}
+
+#[test]
+fn test_streamed_export() {
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .style(|info| WordStyle {
+            fill: Some(if info.rank % 2 == 0 {
+                Color::rgb(200, 0, 0)
+            } else {
+                Color::rgb(0, 0, 200)
+            }),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let words = [
+        ("one", 24),
+        ("two", 20),
+        ("three", 16),
+        ("four", 12),
+        ("five", 8),
+        ("six", 4),
+    ];
+    wc.write_content(test_words(&words), 10).unwrap();
+    assert_eq!(wc.ct.read().iter().count(), words.len());
+
+    let directory = std::env::temp_dir().join(format!("wordcloud-stream-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let (rendered_file, text_file) = (directory.join("rendered.svg"), directory.join("text.svg"));
+    let options = RenderedOptions::new().word_labels(true);
+    wc.write_rendered_to(
+        BufWriter::new(File::create(&rendered_file).unwrap()),
+        &options,
+    )
+    .unwrap();
+    wc.write_text_to(
+        BufWriter::new(File::create(&text_file).unwrap()),
+        &ExportOptions::default(),
+    )
+    .unwrap();
+    let rendered = wc.export_rendered_with(&options).unwrap();
+    assert_eq!(std::fs::read_to_string(&rendered_file).unwrap(), rendered);
+    assert_eq!(
+        std::fs::read_to_string(&text_file).unwrap(),
+        wc.export_text().unwrap()
+    );
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    // one path per style, labelled with its words in rank order
+    assert_eq!(rendered.matches("<path").count(), 2);
+    assert!(rendered.contains(r#"aria-label="one three five""#));
+    assert!(rendered.contains(r#"aria-label="two four six""#));
+
+    let unmerged = wc
+        .export_rendered_with(&options.clone().merge_paths(false))
+        .unwrap();
+    assert_eq!(unmerged.matches("<path").count(), words.len());
+}
+
+#[test]
+fn test_sorted_words() {
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .build()
+        .unwrap();
+    // every word has its own rank, words with the same count are ranked by their text
+    wc.write_content(
+        test_words(&[("beta", 8), ("alpha", 8), ("gamma", 16), ("delta", 4)]),
+        10,
+    )
+    .unwrap();
+
+    let tree = wc.ct.read();
+    let entries = tree
+        .iter()
+        .map(|e| (e.area(), e.value_ref().clone()))
+        .collect::<Vec<(Area<u64>, Word)>>();
+    let order = |entries: &mut dyn Iterator<Item = &(Area<u64>, Word)>| {
+        let mut other = Quadtree::new(tree.depth());
+        for (area, word) in entries {
+            other.insert(*area, word.clone());
+        }
+        WordCloud::sorted_words(&other)
+            .iter()
+            .map(|w| (w.rank, w.text.clone(), w.offset.x, w.offset.y))
+            .collect::<Vec<(usize, String, f32, f32)>>()
+    };
+
+    let forward = order(&mut entries.iter());
+    assert_eq!(forward, order(&mut entries.iter().rev()));
+    let texts = forward
+        .iter()
+        .map(|(_, t, _, _)| t.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(texts, ["gamma", "alpha", "beta", "delta"]);
+    drop(tree);
+    assert_eq!(wc.export_text().unwrap(), wc.export_text().unwrap());
+}
+
+#[test]
+fn test_accessible_export() {
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(800, 800))
+        .font(&font_set)
+        .build()
+        .unwrap();
+    wc.write_content(test_words(&[("first", 16), ("second", 8)]), 10)
+        .unwrap();
+    let export_options = ExportOptions::new()
+        .title("Feedback <2024>")
+        .description("Words & phrases");
+
+    let rendered = wc
+        .export_rendered_with(
+            &RenderedOptions::new()
+                .word_list(true)
+                .export_options(export_options.clone()),
+        )
+        .unwrap();
+    assert!(rendered.contains(r#"role="img""#));
+    assert!(
+        rendered.contains(r#"aria-labelledby="wordcloud-title wordcloud-desc wordcloud-words""#)
+    );
+    let title = rendered.find(r#"<title id="wordcloud-title">"#).unwrap();
+    assert!(rendered[title..]
+        .starts_with("<title id=\"wordcloud-title\">\nFeedback &lt;2024&gt;\n</title>"));
+    let description = rendered.find(r#"<desc id="wordcloud-desc">"#).unwrap();
+    assert!(rendered[description..].contains("Words &amp; phrases\n</desc>"));
+    let list = rendered.find(r#"id="wordcloud-words""#).unwrap();
+    assert!(rendered[list..].contains("first, second\n</text>"));
+
+    let text = wc.export_text_with(&export_options).unwrap();
+    assert!(text.contains(r#"aria-labelledby="wordcloud-title wordcloud-desc""#));
+    // both words use the same font and share a labelled group
+    assert!(text.contains(r#"role="group""#));
+    assert!(text.contains(r#"aria-label="first, second""#));
+    assert!(!text.contains("wordcloud-words"));
+
+    // without any labels, the document isn't announced as an image
+    let plain = wc.export_text().unwrap();
+    assert!(!plain.contains(r#"role="img""#) && !plain.contains("<title"));
+}
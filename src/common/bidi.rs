@@ -0,0 +1,227 @@
+use swash::text::{BidiClass, Codepoint};
+
+/**
+    Embedding levels of the characters of a single word, resolved with the rules of the Unicode
+    bidirectional algorithm (UAX #9). Only a subset of the algorithm is implemented, which is
+    enough for single words:
+
+    - Explicit embeddings, overrides and isolates (rules X1 to X10) are not supported. A word
+      containing one of their formatting characters is kept in logical order, every character
+      gets level 0.
+    - Bracket pairs (rule N0) are not matched. Brackets are resolved like any other neutral by
+      N1 and N2, so a closing bracket may take the direction of the text following it instead
+      of the direction of its opening bracket.
+    - Whitespace at the end of the word is not reset to the paragraph level (rule L1).
+*/
+pub(crate) struct BidiLevels {
+    /// Whether the first strong character is right-to-left
+    pub(crate) rtl: bool,
+    /// Level of every character, odd levels are right-to-left
+    pub(crate) levels: Vec<u8>,
+}
+
+impl BidiLevels {
+    pub(crate) fn resolve(text: &str) -> Self {
+        use BidiClass::*;
+
+        let mut types: Vec<BidiClass> = text.chars().map(|c| c.bidi_class()).collect();
+
+        // X1 to X10 are not implemented, reordering around the explicit formatting characters
+        // would be wrong, so the word stays in logical order
+        if types
+            .iter()
+            .any(|t| matches!(t, LRE | RLE | LRO | RLO | PDF | LRI | RLI | FSI | PDI))
+        {
+            return BidiLevels {
+                rtl: false,
+                levels: vec![0; types.len()],
+            };
+        }
+
+        // P2, P3: the paragraph direction is the direction of the first strong character
+        let rtl = types
+            .iter()
+            .find(|t| matches!(t, L | R | AL))
+            .is_some_and(|t| *t != L);
+        let (base_level, base_type) = if rtl { (1, R) } else { (0, L) };
+
+        // W1: non-spacing marks take the type of the previous character
+        for i in 0..types.len() {
+            if types[i] == NSM {
+                types[i] = if i == 0 { base_type } else { types[i - 1] };
+            }
+        }
+
+        // W2, W3: European numbers after Arabic letters are Arabic numbers
+        let mut last_strong = base_type;
+        for t in types.iter_mut() {
+            match *t {
+                L | R | AL => last_strong = *t,
+                EN if last_strong == AL => *t = AN,
+                _ => {}
+            }
+            if *t == AL {
+                *t = R;
+            }
+        }
+
+        // W4: single separators between two numbers of the same kind
+        for i in 1..types.len().saturating_sub(1) {
+            let (before, after) = (types[i - 1], types[i + 1]);
+            types[i] = match (types[i], before, after) {
+                (ES, EN, EN) | (CS, EN, EN) => EN,
+                (CS, AN, AN) => AN,
+                (t, _, _) => t,
+            };
+        }
+
+        // W5: terminators next to European numbers
+        for i in 0..types.len() {
+            if types[i] != ET {
+                continue;
+            }
+            let end = (i..types.len())
+                .find(|j| types[*j] != ET)
+                .unwrap_or(types.len());
+            let next_to_number = (i > 0 && types[i - 1] == EN) || types.get(end) == Some(&EN);
+            if next_to_number {
+                types[i..end].iter_mut().for_each(|t| *t = EN);
+            }
+        }
+
+        // W6, W7: remaining separators are neutral, European numbers after L are L
+        let mut last_strong = base_type;
+        for t in types.iter_mut() {
+            match *t {
+                ES | ET | CS => *t = ON,
+                L | R => last_strong = *t,
+                EN if last_strong == L => *t = L,
+                _ => {}
+            }
+        }
+
+        // N1, N2: neutrals between characters of the same direction take that direction,
+        // otherwise the paragraph direction
+        let direction = |t: BidiClass| match t {
+            L => Some(L),
+            R | AN | EN => Some(R),
+            _ => None,
+        };
+        let mut i = 0;
+        while i < types.len() {
+            if direction(types[i]).is_some() {
+                i += 1;
+                continue;
+            }
+            let end = (i..types.len())
+                .find(|j| direction(types[*j]).is_some())
+                .unwrap_or(types.len());
+            let before = if i == 0 {
+                base_type
+            } else {
+                direction(types[i - 1]).unwrap_or(base_type)
+            };
+            let after = types
+                .get(end)
+                .and_then(|t| direction(*t))
+                .unwrap_or(base_type);
+            let resolved = if before == after { before } else { base_type };
+            types[i..end].iter_mut().for_each(|t| *t = resolved);
+            i = end;
+        }
+
+        // I1, I2
+        let levels = types
+            .iter()
+            .map(|t| match (base_level, t) {
+                (0, R) => 1,
+                (0, AN | EN) => 2,
+                (1, L | AN | EN) => 2,
+                (level, _) => level,
+            })
+            .collect();
+
+        BidiLevels { rtl, levels }
+    }
+}
+
+/**
+    Visual order of consecutive runs with the given levels, from left to right (rule L2)
+*/
+pub(crate) fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = levels
+        .iter()
+        .copied()
+        .filter(|l| l % 2 == 1)
+        .min()
+        .unwrap_or(highest + 1);
+
+    for level in (lowest_odd..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let end = (i..order.len())
+                .find(|j| levels[order[*j]] < level)
+                .unwrap_or(order.len());
+            order[i..end].reverse();
+            i = end;
+        }
+    }
+    order
+}
+
+#[test]
+fn test_bidi_levels() {
+    // Hebrew with a number: the number stays left-to-right inside the right-to-left word
+    let resolved = BidiLevels::resolve("שלום-42");
+    assert!(resolved.rtl);
+    assert_eq!(resolved.levels, vec![1, 1, 1, 1, 1, 2, 2]);
+
+    let resolved = BidiLevels::resolve("data→שלום");
+    assert!(!resolved.rtl);
+    assert_eq!(resolved.levels, vec![0, 0, 0, 0, 0, 1, 1, 1, 1]);
+
+    // brackets take the direction of the text around them
+    let resolved = BidiLevels::resolve("abc(שלום)");
+    assert!(!resolved.rtl);
+    assert_eq!(resolved.levels, vec![0, 0, 0, 0, 1, 1, 1, 1, 0]);
+    let resolved = BidiLevels::resolve("שלום(abc)");
+    assert_eq!(resolved.levels, vec![1, 1, 1, 1, 1, 2, 2, 2, 1]);
+    assert_eq!(
+        visual_order(&resolved.levels),
+        vec![8, 5, 6, 7, 4, 3, 2, 1, 0]
+    );
+
+    // numbers in brackets inside right-to-left text, and after Arabic letters
+    let resolved = BidiLevels::resolve("(12)שלום");
+    assert!(resolved.rtl);
+    assert_eq!(resolved.levels, vec![1, 2, 2, 1, 1, 1, 1, 1]);
+    assert_eq!(BidiLevels::resolve("سعر 12").levels, vec![1, 1, 1, 1, 2, 2]);
+    assert_eq!(BidiLevels::resolve("abc-12").levels, vec![0; 6]);
+
+    // without N0, the closing bracket follows the left-to-right text behind it, where UAX #9
+    // resolves it to right-to-left like its opening bracket
+    assert_eq!(BidiLevels::resolve("ש(a)b").levels, vec![1, 1, 2, 2, 2]);
+
+    // explicit embeddings, overrides and isolates fall back to logical order
+    for control in [
+        '\u{202A}', '\u{202B}', '\u{202D}', '\u{202E}', '\u{2067}', '\u{2068}',
+    ] {
+        let resolved = BidiLevels::resolve(&format!("{}שלום-42", control));
+        assert!(!resolved.rtl);
+        assert_eq!(resolved.levels, vec![0; 8]);
+        assert_eq!(visual_order(&resolved.levels), (0..8).collect::<Vec<_>>());
+    }
+    let resolved = BidiLevels::resolve("abc\u{2067}שלום\u{2069}");
+    assert_eq!(resolved.levels, vec![0; 9]);
+    // marks are no explicit formatting characters
+    assert!(BidiLevels::resolve("\u{200F}שלום").rtl);
+
+    assert_eq!(visual_order(&[1, 2, 1]), vec![2, 1, 0]);
+    assert_eq!(visual_order(&[0, 1, 1, 0]), vec![0, 2, 1, 3]);
+}
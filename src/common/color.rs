@@ -0,0 +1,238 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+#[cfg(feature = "background_image")]
+use image::Rgba;
+
+/**
+    A RGBA color used for the words of the [`crate::WordCloud`].
+*/
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    /**
+        Create an opaque color from its red, green and blue channels.
+    */
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    /**
+        Create a color from its red, green, blue and alpha channels.
+    */
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /**
+        Create an opaque color from hue (in degrees), saturation and lightness (both `0.0..=1.0`).
+    */
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (s, l) = (saturation.clamp(0., 1.), lightness.clamp(0., 1.));
+        let h = hue.rem_euclid(360.) / 60.;
+
+        let chroma = (1. - (2. * l - 1.).abs()) * s;
+        let x = chroma * (1. - (h % 2. - 1.).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.),
+            1 => (x, chroma, 0.),
+            2 => (0., chroma, x),
+            3 => (0., x, chroma),
+            4 => (x, 0., chroma),
+            _ => (chroma, 0., x),
+        };
+        let m = l - chroma / 2.;
+
+        Color::rgb(
+            ((r + m) * 255.).round() as u8,
+            ((g + m) * 255.).round() as u8,
+            ((b + m) * 255.).round() as u8,
+        )
+    }
+
+    /**
+        Linear interpolation between `self` (at `t = 0`) and `other` (at `t = 1`).
+    */
+    pub fn mix(&self, other: &Color, t: f32) -> Color {
+        let t = t.clamp(0., 1.);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: channel(self.a, other.a),
+        }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rgb({}, {}, {})", self.r, self.g, self.b)
+    }
+}
+
+#[cfg(feature = "background_image")]
+impl From<Rgba<u8>> for Color {
+    fn from(value: Rgba<u8>) -> Self {
+        Color::rgba(value.0[0], value.0[1], value.0[2], value.0[3])
+    }
+}
+
+/**
+    The ten colors of the "Tableau 10" palette.
+*/
+pub const TABLEAU_10: [Color; 10] = [
+    Color::rgb(78, 121, 167),
+    Color::rgb(242, 142, 43),
+    Color::rgb(225, 87, 89),
+    Color::rgb(118, 183, 178),
+    Color::rgb(89, 161, 79),
+    Color::rgb(237, 201, 72),
+    Color::rgb(176, 122, 161),
+    Color::rgb(255, 157, 167),
+    Color::rgb(156, 117, 95),
+    Color::rgb(186, 176, 172),
+];
+
+/**
+    A palette of blue tones, from dark to light.
+*/
+pub const OCEAN: [Color; 5] = [
+    Color::rgb(3, 4, 94),
+    Color::rgb(0, 119, 182),
+    Color::rgb(0, 180, 216),
+    Color::rgb(72, 202, 228),
+    Color::rgb(144, 224, 239),
+];
+
+/**
+    Decides the color of each word. Set it with [`crate::WordCloudBuilder::color_scheme`].
+*/
+#[derive(Clone, Debug)]
+pub enum ColorScheme {
+    /// Every word gets the same color.
+    Fixed(Color),
+    /// The words cycle through the palette in the order of their rank.
+    Palette(Vec<Color>),
+    /// Each word gets a random color from the palette. The same `seed` and word always yield
+    /// the same color.
+    RandomPalette { palette: Vec<Color>, seed: u64 },
+    /// Interpolates from the first color (most common word) to the second color (least common
+    /// word) by rank.
+    RankGradient(Color, Color),
+    /// Interpolates from the first color (lowest weight) to the second color (highest weight)
+    /// by the relative word count.
+    WeightGradient(Color, Color),
+    /// Generates random colors in the HSL color space. Hues are in degrees, saturation and
+    /// lightness in `0.0..=1.0`.
+    Hsl {
+        hue: Range<f32>,
+        saturation: Range<f32>,
+        lightness: Range<f32>,
+        seed: u64,
+    },
+}
+
+/**
+    64 bit FNV-1a hash of `bytes`
+*/
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+impl ColorScheme {
+    /**
+        Random number generator for a word, seeded with `seed` and the 64 bit FNV-1a hash of the
+        text. Unlike the hasher of the standard library, the hash doesn't change between Rust
+        releases, so the colors stay the same.
+    */
+    fn rng_for_word(seed: u64, text: &str) -> SmallRng {
+        SmallRng::seed_from_u64(seed ^ fnv1a(text.as_bytes()))
+    }
+
+    fn sample(rng: &mut SmallRng, range: &Range<f32>) -> f32 {
+        if range.is_empty() {
+            range.start
+        } else {
+            rng.gen_range(range.clone())
+        }
+    }
+
+    /**
+        Color for a word with the given `rank` (0 being the most common word) out of
+        `rank_count` words and the `weight` (its count relative to the most common word).
+    */
+    pub(crate) fn color(&self, text: &str, rank: usize, rank_count: usize, weight: f32) -> Color {
+        match self {
+            ColorScheme::Fixed(c) => *c,
+            ColorScheme::Palette(palette) => match palette.len() {
+                0 => Color::rgb(0, 0, 0),
+                len => palette[rank % len],
+            },
+            ColorScheme::RandomPalette { palette, seed } => match palette.len() {
+                0 => Color::rgb(0, 0, 0),
+                len => palette[ColorScheme::rng_for_word(*seed, text).gen_range(0..len)],
+            },
+            ColorScheme::RankGradient(from, to) => {
+                let t = if rank_count > 1 {
+                    rank as f32 / (rank_count - 1) as f32
+                } else {
+                    0.
+                };
+                from.mix(to, t)
+            }
+            ColorScheme::WeightGradient(from, to) => from.mix(to, weight),
+            ColorScheme::Hsl {
+                hue,
+                saturation,
+                lightness,
+                seed,
+            } => {
+                let mut rng = ColorScheme::rng_for_word(*seed, text);
+                Color::hsl(
+                    ColorScheme::sample(&mut rng, hue),
+                    ColorScheme::sample(&mut rng, saturation),
+                    ColorScheme::sample(&mut rng, lightness),
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn test_color_scheme() {
+    assert_eq!(Color::hsl(0., 1., 0.5), Color::rgb(255, 0, 0));
+    assert_eq!(Color::hsl(240., 1., 0.5), Color::rgb(0, 0, 255));
+
+    let gradient = ColorScheme::RankGradient(Color::rgb(0, 0, 0), Color::rgb(200, 100, 0));
+    assert_eq!(gradient.color("a", 0, 3, 1.), Color::rgb(0, 0, 0));
+    assert_eq!(gradient.color("b", 1, 3, 0.5), Color::rgb(100, 50, 0));
+    assert_eq!(gradient.color("c", 2, 3, 0.1), Color::rgb(200, 100, 0));
+
+    let random = ColorScheme::RandomPalette {
+        palette: TABLEAU_10.to_vec(),
+        seed: 7,
+    };
+    assert_eq!(
+        random.color("word", 0, 1, 1.),
+        random.color("word", 5, 9, 0.2)
+    );
+
+    assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+}
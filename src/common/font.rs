@@ -1,16 +1,27 @@
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::io::Cursor;
+use std::ops::Range;
+use std::path::Path;
 
 use std::sync::Arc;
+
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use swash::text::{Codepoint};
 
 use swash::scale::ScaleContext;
-use swash::{FontRef, StringId, Tag};
+use swash::{tag_from_str_lossy, FontDataRef, FontRef, StringId, Tag};
 
 #[cfg(feature = "woff2")]
 use woff2::convert_woff2_to_ttf;
 
+use crate::common::color::fnv1a;
+use crate::common::font_index::FontDescription;
+pub use crate::common::font_index::{FontEntry, FontIndex, Weight};
+
 #[derive(Clone)]
 #[allow(clippy::upper_case_acronyms)]
 pub(crate) enum FontType {
@@ -34,7 +45,69 @@ impl FontType {
 /**
     Error returned if font loading failed
 */
-pub type FontLoadingError = String;
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FontLoadingError {
+    /// The data is too short to contain the magic bytes of a font
+    TooShort,
+    /// The magic bytes don't belong to a supported font format
+    UnknownFormat([u8; 4]),
+    /// The font format needs a feature, which isn't enabled
+    FeatureDisabled(&'static str),
+    /// Converting a WOFF or WOFF2 font failed
+    Decompression(String),
+    /// The table directory of the font couldn't be read, or tables needed for shaping and
+    /// scaling are missing
+    MissingTables,
+    /// The collection has no face with this index
+    FaceIndex(usize),
+    /// Reading a font file failed
+    Io(std::io::Error),
+    /// The directory doesn't contain any fonts, which could be loaded, or no font was added to
+    /// the [`FontSetBuilder`]
+    NoFonts,
+}
+
+impl Display for FontLoadingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontLoadingError::TooShort => write!(f, "the font data is too short"),
+            FontLoadingError::UnknownFormat(magic) => {
+                write!(f, "unrecognized font magic {:?}", magic)
+            }
+            FontLoadingError::FeatureDisabled(feature) => {
+                write!(f, "activate the {} feature for this font", feature)
+            }
+            FontLoadingError::Decompression(e) => write!(f, "font conversion failed: {}", e),
+            FontLoadingError::MissingTables => write!(f, "the font tables couldn't be read"),
+            FontLoadingError::FaceIndex(index) => {
+                write!(f, "no font at index {} of the collection", index)
+            }
+            FontLoadingError::Io(e) => write!(f, "reading the font failed: {}", e),
+            FontLoadingError::NoFonts => write!(f, "no fonts to build the font set from"),
+        }
+    }
+}
+
+impl std::error::Error for FontLoadingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontLoadingError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FontLoadingError {
+    fn from(e: std::io::Error) -> Self {
+        FontLoadingError::Io(e)
+    }
+}
+
+/**
+    Tables, without which a font can't be shaped or measured
+*/
+const REQUIRED_TABLES: [&str; 5] = ["cmap", "head", "hhea", "hmtx", "maxp"];
 
 /**
     Result from [`FontLoadingError`]
@@ -42,15 +115,18 @@ pub type FontLoadingError = String;
 pub type FontLoadingResult<T> = Result<T, FontLoadingError>;
 
 /**
-    Represents a Font stored in memory. By default, it supports `OTF` and `TTF` fonts, with
-    the create features `woff` and `woff2` it also supports loading `WOFF` fonts.
+    Represents a Font stored in memory. By default, it supports `OTF` and `TTF` fonts and
+    collections of them, with the create features `woff` and `woff2` it also supports loading
+    `WOFF` fonts.
 */
 pub struct Font<'a> {
     name: String,
+    description: FontDescription,
     re: FontRef<'a>,
     font_type: FontType,
     supported_scripts: HashSet<CScript>,
     packed_font_data: Option<Vec<u8>>,
+    variation_axes: Vec<VariationAxis>,
     _approximate_pixel_width: f32,
 }
 
@@ -87,7 +163,10 @@ impl<'a> Font<'a> {
         after calling this function.
     */
     pub fn from_data(data: &'a mut Vec<u8>) -> FontLoadingResult<Self> {
-        assert!(data.len() >= 4);
+        let magic: [u8; 4] = match data.get(0..4) {
+            Some(magic) => magic.try_into().unwrap_or_default(),
+            None => return Err(FontLoadingError::TooShort),
+        };
         let (font_type, re, packed_data) = if &data[0..4] == b"\x00\x01\x00\x00" {
             (FontType::TTF, FontRef::from_index(data, 0), None)
         } else if &data[0..4] == b"OTTO" {
@@ -97,7 +176,7 @@ impl<'a> Font<'a> {
             {
                 let cv = match convert_woff2_to_ttf(&mut data.as_slice()) {
                     Ok(c) => c,
-                    Err(e) => return Err(e.to_string()),
+                    Err(e) => return Err(FontLoadingError::Decompression(e.to_string())),
                 };
                 let pack = data.clone();
 
@@ -107,12 +186,12 @@ impl<'a> Font<'a> {
                 (FontType::WOFF2, FontRef::from_index(data, 0), Some(pack))
             }
             #[cfg(not(feature = "woff2"))]
-            unimplemented!("activate the woff2 feature for this font")
+            return Err(FontLoadingError::FeatureDisabled("woff2"));
         } else if &data[0..4] == b"wOFF" {
             let mut inp_cur = Cursor::new(&data);
             let mut out_cur = Cursor::new(Vec::new());
             rs_woff::woff2otf(&mut inp_cur, &mut out_cur)
-                .expect("font conversion from woff1 unsuccessful");
+                .map_err(|e| FontLoadingError::Decompression(e.to_string()))?;
 
             let pack = data.clone();
 
@@ -120,15 +199,82 @@ impl<'a> Font<'a> {
             data.extend_from_slice(out_cur.get_ref().as_slice());
 
             (FontType::WOFF, FontRef::from_index(data, 0), Some(pack))
+        } else if &data[0..4] == b"ttcf" {
+            return Font::from_collection(data, 0);
         } else {
-            unimplemented!("unrecognized font magic {:?}", &data[0..4]);
+            return Err(FontLoadingError::UnknownFormat(magic));
         };
 
         let re = match re {
-            None => return Err(FontLoadingError::from("loading font failed")),
+            None => return Err(FontLoadingError::MissingTables),
             Some(e) => e,
         };
 
+        Font::from_reference(re, font_type, packed_data)
+    }
+
+    /**
+        Load the face at `index` of a TrueType or OpenType collection (`.ttc` or `.otc`), see
+        [`Font::collection_faces`] for the names of the faces. A single font is accepted as a
+        collection with one face.
+    */
+    pub fn from_collection(data: &'a [u8], index: usize) -> FontLoadingResult<Self> {
+        let re = match FontRef::from_index(data, index) {
+            None => return Err(FontLoadingError::FaceIndex(index)),
+            Some(re) => re,
+        };
+        let font_type = match data.get(re.offset as usize..re.offset as usize + 4) {
+            Some(b"OTTO") => FontType::OTF,
+            _ => FontType::TTF,
+        };
+
+        Font::from_reference(re, font_type, None)
+    }
+
+    /**
+        Load all faces of a TrueType or OpenType collection, see [`Font::from_collection`]
+    */
+    pub fn collection(data: &'a [u8]) -> FontLoadingResult<Vec<Self>> {
+        let count = FontDataRef::new(data).map_or(0, |d| d.len());
+        if count == 0 {
+            return Err(FontLoadingError::MissingTables);
+        }
+        (0..count).map(|i| Font::from_collection(data, i)).collect()
+    }
+
+    /**
+        Full names of the faces in a TrueType or OpenType collection, e.g. `Noto Sans CJK JP Bold`,
+        in the order of their index
+    */
+    pub fn collection_faces(data: &[u8]) -> Vec<String> {
+        FontDataRef::new(data)
+            .map(|d| {
+                d.fonts()
+                    .map(|f| {
+                        let strings = f.localized_strings();
+                        strings
+                            .find_by_id(StringId::Full, Some("en"))
+                            .or_else(|| strings.find_by_id(StringId::PostScript, None))
+                            .map(|s| s.to_string())
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn from_reference(
+        re: FontRef<'a>,
+        font_type: FontType,
+        packed_data: Option<Vec<u8>>,
+    ) -> FontLoadingResult<Self> {
+        if REQUIRED_TABLES
+            .iter()
+            .any(|t| re.table(tag_from_str_lossy(t)).is_none())
+        {
+            return Err(FontLoadingError::MissingTables);
+        }
+
         let font_name = match re
             .localized_strings()
             .find(|s| s.id() == StringId::PostScript)
@@ -140,15 +286,20 @@ impl<'a> Font<'a> {
         let mut scale_context = ScaleContext::new();
         let mut scaler = scale_context.builder(re).size(20_f32).build();
         let glyph_id = re.charmap().map('a');
-        let outline = scaler.scale_outline(glyph_id).unwrap();
+        // symbol and bitmap fonts may have no outline for 'a'
+        let approximate_pixel_width = scaler
+            .scale_outline(glyph_id)
+            .map_or(0., |outline| outline.bounds().width() / 20.);
 
         Ok(Font {
             name: font_name,
+            description: FontDescription::of(&re),
             re,
             font_type,
             supported_scripts: Font::identify_scripts_in_font(&re),
             packed_font_data: packed_data,
-            _approximate_pixel_width: outline.bounds().width() / 20.,
+            variation_axes: Vec::new(),
+            _approximate_pixel_width: approximate_pixel_width,
         })
     }
 
@@ -164,6 +315,62 @@ impl<'a> Font<'a> {
         &self.name
     }
 
+    /**
+        Family name of the font, e.g. `DejaVu Sans`
+    */
+    pub fn family(&self) -> &str {
+        &self.description.family
+    }
+
+    pub fn weight(&self) -> Weight {
+        self.description.weight
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.description.italic
+    }
+
+    /**
+        Whether the font has a glyph for `c`
+    */
+    pub(crate) fn covers(&self, c: char) -> bool {
+        self.re.charmap().map(c) != 0
+    }
+
+    /**
+        Varies an axis of a variable font with the weight or rank of the words, see
+        [`VariationAxis`]. Axes, which the font doesn't have, are ignored.
+    */
+    pub fn variation(mut self, axis: VariationAxis) -> Self {
+        self.variation_axes.retain(|a| a.tag != axis.tag);
+        self.variation_axes.push(axis);
+        self
+    }
+
+    /**
+        Values of the variation axes for a word with the relative `weight` and `rank`, limited
+        to the range of the axes in the font
+    */
+    pub(crate) fn variations(&self, weight: f32, rank: f32) -> Vec<(Tag, f32)> {
+        let axes = self.re.variations();
+        self.variation_axes
+            .iter()
+            .filter_map(|axis| {
+                let font_axis = axes.find_by_tag(axis.tag)?;
+                let t = match axis.source {
+                    VariationSource::Weight => weight,
+                    VariationSource::Rank => rank,
+                }
+                .clamp(0., 1.);
+                let value = axis.range.start + (axis.range.end - axis.range.start) * t;
+                Some((
+                    axis.tag,
+                    value.clamp(font_axis.min_value(), font_axis.max_value()),
+                ))
+            })
+            .collect()
+    }
+
     pub(crate) fn packed(&self) -> &Option<Vec<u8>> {
         &self.packed_font_data
     }
@@ -174,6 +381,52 @@ impl<'a> Font<'a> {
     }
 }
 
+/**
+    Property of a word, which drives a [`VariationAxis`]
+*/
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VariationSource {
+    /// Count of the word relative to the most frequent word
+    Weight,
+    /// Rank of the word, the first word gets the end and the last word the start of the range
+    Rank,
+}
+
+/**
+    Maps a variation axis of a variable font to the words, e.g. `wght` from 300 for the least to
+    900 for the most frequent word, so that bigger words are also bolder or wider.
+
+    ```no_run
+    use wordcloud::font::{Font, VariationAxis, VariationSource};
+
+    let mut data = std::fs::read("RobotoFlex.ttf").unwrap();
+    let font = Font::from_data(&mut data)
+        .unwrap()
+        .variation(VariationAxis::new("wght", 300. ..900., VariationSource::Weight))
+        .variation(VariationAxis::new("wdth", 75. ..100., VariationSource::Rank));
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct VariationAxis {
+    tag: Tag,
+    range: Range<f32>,
+    source: VariationSource,
+}
+
+impl VariationAxis {
+    /**
+        Varies the axis `tag` from `range.start` for the least to `range.end` for the most
+        prominent word
+    */
+    pub fn new(tag: &str, range: Range<f32>, source: VariationSource) -> Self {
+        VariationAxis {
+            tag: tag_from_str_lossy(tag),
+            range,
+            source,
+        }
+    }
+}
+
 impl<'a> PartialEq<Self> for Font<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.name.eq(&other.name)
@@ -198,11 +451,209 @@ pub struct FontSet<'a> {
 }
 
 impl<'a> FontSet<'a> {
-    pub(crate) fn get_font_for_script(&self, script: &CScript) -> Option<&Font> {
+    /**
+        Loads all fonts in `directory` and its subdirectories, see [`FontIndex::from_directory`].
+        The data of the fonts is appended to `buffers`, which has to outlive the [`FontSet`].
+        Fonts, which can't be read or loaded, are skipped. Fails with
+        [`FontLoadingError::NoFonts`], if none of the fonts could be loaded.
+
+        ```no_run
+        use wordcloud::font::{FontSet, Weight};
+
+        let mut buffers = Vec::new();
+        let font_set = FontSet::from_directory("assets/fonts", &mut buffers).unwrap();
+        let bold = font_set.find("Noto Sans", Weight::Bold);
+        ```
+    */
+    pub fn from_directory<P: AsRef<Path>>(
+        directory: P,
+        buffers: &'a mut Vec<Vec<u8>>,
+    ) -> FontLoadingResult<FontSet<'a>> {
+        let index = FontIndex::from_directory(directory)?;
+
+        // the faces of a collection share the data of their file
+        let mut files: Vec<(&FontEntry, Vec<usize>)> = Vec::new();
+        for entry in index.entries() {
+            match files.last_mut() {
+                Some((file, faces)) if file.path() == entry.path() => faces.push(entry.index()),
+                _ => files.push((entry, vec![entry.index()])),
+            }
+        }
+        let first = buffers.len();
+        files.retain(|(entry, _)| match entry.read() {
+            Ok(data) => {
+                buffers.push(data);
+                true
+            }
+            Err(_) => false,
+        });
+
+        let mut fonts = Vec::new();
+        for (data, (entry, faces)) in buffers.iter_mut().skip(first).zip(&files) {
+            if entry.is_collection() {
+                let data: &'a [u8] = data;
+                fonts.extend(
+                    faces
+                        .iter()
+                        .filter_map(|face| Font::from_collection(data, *face).ok()),
+                );
+            } else if let Ok(font) = Font::from_data(data) {
+                fonts.push(font);
+            }
+        }
+        FontSetBuilder::new().extend(fonts).build()
+    }
+
+    /**
+        Finds the font of `family` with the weight closest to `weight`, see [`FontIndex::find`]
+    */
+    pub fn find(&self, family: &str, weight: Weight) -> Option<&Font<'a>> {
+        self.inner
+            .iter()
+            .filter(|f| f.description.is_family(family))
+            .min_by_key(|f| f.description.distance(weight))
+    }
+
+    pub(crate) fn get_font_for_script(&self, script: &CScript) -> Option<&Font<'a>> {
         self.inner
             .iter()
             .find(|f| f.supported_scripts.contains(script))
     }
+
+    /**
+        Font for the script of the word described by `info`, chosen with `selection`. Fonts,
+        which don't support the script, are never chosen.
+    */
+    pub(crate) fn select_font<'s>(
+        &'s self,
+        info: &FontSelectionInfo,
+        selection: &FontSelection<'a>,
+    ) -> Option<&'s Font<'a>> {
+        let script = info.text.guess_script();
+        let (candidates, seed): (Vec<&Font<'a>>, u64) = match selection {
+            FontSelection::FirstMatch => return self.get_font_for_script(&script),
+            FontSelection::Random { seed } => (self.inner.iter().collect(), *seed),
+            FontSelection::Custom(hook) => {
+                (hook(info).into_iter().map(|f| f as &Font<'a>).collect(), 0)
+            }
+        };
+        // like the random colors, the choice only depends on the seed and the word
+        let mut rng = SmallRng::seed_from_u64(seed ^ fnv1a(info.text.as_bytes()));
+
+        candidates
+            .into_iter()
+            .filter(|f| f.supported_scripts.contains(&script))
+            .collect::<Vec<_>>()
+            .choose(&mut rng)
+            .copied()
+            .or_else(|| self.get_font_for_script(&script))
+    }
+
+    /**
+        Splits `text` into runs, which are each written with one font. A character stays in the
+        run before it, as long as its font has a glyph for it, so that digits and punctuation
+        don't start new runs. Otherwise, the `primary` font of the word or the first font with a
+        glyph for it is used. Returns `None`, if no font has a glyph for any character.
+    */
+    pub(crate) fn font_runs<'s>(
+        &'s self,
+        text: &str,
+        primary: Option<&'s Font<'a>>,
+    ) -> Option<Vec<(Range<usize>, &'s Font<'a>)>> {
+        let mut runs: Vec<(Range<usize>, &Font<'a>)> = Vec::new();
+        let mut covered = false;
+
+        for (i, c) in text.char_indices() {
+            let current = runs.last().map(|(_, f)| *f);
+            let font = current
+                .filter(|f| f.covers(c))
+                .or(primary.filter(|f| f.covers(c)))
+                .or_else(|| self.inner.iter().find(|f| f.covers(c)));
+            covered |= font.is_some();
+
+            // characters without a glyph in any font stay in the current run
+            let font = font.or(current).or(primary).unwrap_or(&self.inner[0]);
+            match runs.last_mut() {
+                Some((range, f)) if *f == font => range.end = i + c.len_utf8(),
+                _ => runs.push((i..i + c.len_utf8(), font)),
+            }
+        }
+
+        if covered {
+            Some(runs)
+        } else {
+            None
+        }
+    }
+}
+
+/**
+    Information about a word, before it is written to the [`crate::WordCloud`]. Passed to the hook
+    of [`FontSelection::Custom`].
+*/
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct FontSelectionInfo<'w> {
+    /// The word itself
+    pub text: &'w str,
+    /// Number of occurrences in the [`crate::RankedWords`]
+    pub count: usize,
+    /// Position in the [`crate::RankedWords`], starting with `0` for the most common word
+    pub rank: usize,
+    /// Count relative to the most common word, in `0.0..=1.0`
+    pub weight: f32,
+    /// Region of the [`crate::RegionMask`] the word is written to
+    pub region: Option<usize>,
+}
+
+pub(crate) type FontSelectionFn<'a> =
+    Box<dyn Fn(&FontSelectionInfo) -> Vec<&'a Font<'a>> + Send + Sync + 'a>;
+
+/**
+    Decides, which font of the [`FontSet`] is used for a word, see
+    [`crate::WordCloudBuilder::font_selection`]. Only fonts supporting the script of the word
+    are chosen, characters missing in the chosen font are still written with another font.
+
+    ```no_run
+    use wordcloud::font::{FontSelection, Weight};
+    # use wordcloud::font::FontSet;
+    #
+    # let mut buffers = Vec::new();
+    # let font_set = FontSet::from_directory("fonts", &mut buffers).unwrap();
+
+    let bebas = font_set.find("Bebas Neue", Weight::Bold).unwrap();
+    let inter = font_set.find("Inter", Weight::Regular).unwrap();
+
+    // the top 10 words in Bebas Neue, the rest in Inter
+    let selection = FontSelection::custom(move |word| match word.rank {
+        0..=9 => vec![bebas],
+        _ => vec![inter],
+    });
+    ```
+*/
+#[derive(Default)]
+pub enum FontSelection<'a> {
+    /// The first font supporting the script of the word
+    #[default]
+    FirstMatch,
+    /// A random font supporting the script of the word. The same `seed` and word always yield
+    /// the same font.
+    Random { seed: u64 },
+    /// A random font of the ones returned by the hook for the word, which supports its script.
+    /// The same fonts and word always yield the same font. Falls back to
+    /// [`FontSelection::FirstMatch`], if none of them supports it.
+    Custom(FontSelectionFn<'a>),
+}
+
+impl<'a> FontSelection<'a> {
+    /**
+        Construct a [`FontSelection::Custom`] from the hook
+    */
+    pub fn custom(
+        hook: impl Fn(&FontSelectionInfo) -> Vec<&'a Font<'a>> + Send + Sync + 'a,
+    ) -> Self {
+        FontSelection::Custom(Box::new(hook))
+    }
 }
 
 /**
@@ -215,7 +666,8 @@ impl<'a> FontSet<'a> {
     // let fonts = ...;
     let font_set: FontSet = FontSetBuilder::new()
         .extend(fonts)
-        .build();
+        .build()
+        .unwrap();
     ```
 */
 #[derive(Default)]
@@ -257,15 +709,16 @@ impl<'a> FontSetBuilder<'a> {
     }
 
     /**
-        Build a [`FontSet`] from the fonts. Panics, if no font was provided.
+        Build a [`FontSet`] from the fonts. Fails with [`FontLoadingError::NoFonts`], if no font
+        was provided.
     */
-    pub fn build(self) -> FontSet<'a> {
+    pub fn build(self) -> FontLoadingResult<FontSet<'a>> {
         if self.fonts.is_empty() {
-            panic!("At least one font needs to be provided.");
+            return Err(FontLoadingError::NoFonts);
         }
-        FontSet {
+        Ok(FontSet {
             inner: Arc::new(self.fonts),
-        }
+        })
     }
 }
 
@@ -333,3 +786,253 @@ impl GuessScript for &str {
         }
     }
 }
+
+#[test]
+fn test_variations() {
+    use crate::export::subset::Sfnt;
+
+    // Open Sans with a weight axis from 100 to 700, without any variation data
+    let mut sfnt = Sfnt::parse(
+        include_bytes!("../../example/assets/OpenSans-Regular.ttf"),
+        0,
+    )
+    .unwrap();
+    let fixed = |v: i32| (v << 16).to_be_bytes();
+    let fvar = [
+        [0, 1, 0, 0, 0, 16, 0, 2, 0, 1, 0, 20, 0, 0, 0, 8].as_slice(),
+        b"wght",
+        &fixed(100),
+        &fixed(400),
+        &fixed(700),
+        &[0, 0, 1, 0],
+    ]
+    .concat();
+    sfnt.tables.insert(u32::from_be_bytes(*b"fvar"), fvar);
+    let mut data = sfnt.write();
+
+    let font = Font::from_data(&mut data)
+        .unwrap()
+        .variation(VariationAxis::new(
+            "wght",
+            300. ..900.,
+            VariationSource::Weight,
+        ))
+        .variation(VariationAxis::new(
+            "wdth",
+            75. ..100.,
+            VariationSource::Rank,
+        ));
+
+    // the missing width axis is ignored and the weight is limited to the axis of the font
+    let wght = tag_from_str_lossy("wght");
+    assert_eq!(font.variations(0.25, 1.), vec![(wght, 450.)]);
+    assert_eq!(font.variations(1., 0.), vec![(wght, 700.)]);
+}
+
+#[test]
+fn test_font_selection() {
+    let mut data = Vec::from(include_bytes!("../../example/assets/OpenSans-Regular.ttf") as &[u8]);
+    let font_set = FontSetBuilder::new()
+        .push(Font::from_data(&mut data).unwrap())
+        .build()
+        .unwrap();
+    let open_sans = font_set.find("Open Sans", Weight::Regular).unwrap();
+    let selection = FontSelection::custom(move |word| match word.rank {
+        0 => vec![open_sans],
+        _ => vec![],
+    });
+
+    let info = |text, rank| FontSelectionInfo {
+        text,
+        count: 1,
+        rank,
+        weight: 1.,
+        region: None,
+    };
+    assert!(font_set.select_font(&info("first", 0), &selection) == Some(open_sans));
+    assert!(font_set.select_font(&info("second", 1), &selection) == Some(open_sans));
+    // Open Sans has no Han, so it is never chosen for Chinese words
+    assert!(font_set.select_font(&info("漢字", 0), &selection).is_none());
+    assert!(font_set
+        .select_font(&info("漢字", 0), &FontSelection::Random { seed: 0 })
+        .is_none());
+
+    // the same seed picks the same fonts, another seed picks others
+    let fonts = (0..4)
+        .map(|i| {
+            let data = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+            let mut font = Font::from_collection(data, 0).unwrap();
+            font.name = format!("Open Sans {}", i);
+            font
+        })
+        .collect();
+    let font_set = FontSetBuilder::new().extend(fonts).build().unwrap();
+    let words = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    ];
+    let pick = |seed| {
+        words
+            .iter()
+            .map(|w| {
+                let selection = FontSelection::Random { seed };
+                let font = font_set.select_font(&info(w, 0), &selection).unwrap();
+                font.name.clone()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(pick(7), pick(7));
+    assert_ne!(pick(7), pick(8));
+    assert!(pick(7).iter().collect::<HashSet<_>>().len() > 1);
+}
+
+#[test]
+fn test_font_loading_error() {
+    let mut data = b"ab".to_vec();
+    assert!(matches!(
+        Font::from_data(&mut data),
+        Err(FontLoadingError::TooShort)
+    ));
+
+    let mut data = b"GIF89a".to_vec();
+    assert!(matches!(
+        Font::from_data(&mut data),
+        Err(FontLoadingError::UnknownFormat(magic)) if &magic == b"GIF8"
+    ));
+
+    // a truncated table directory
+    let mut data = b"\x00\x01\x00\x00\x00\x05".to_vec();
+    assert!(matches!(
+        Font::from_data(&mut data),
+        Err(FontLoadingError::MissingTables)
+    ));
+
+    let mut data = b"wOFF\x00\x00".to_vec();
+    assert!(matches!(
+        Font::from_data(&mut data),
+        Err(FontLoadingError::Decompression(_))
+    ));
+
+    let data = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    assert!(matches!(
+        Font::from_collection(data, 3),
+        Err(FontLoadingError::FaceIndex(3))
+    ));
+
+    // an empty font set is an error instead of a panic
+    assert!(matches!(
+        FontSetBuilder::new().build(),
+        Err(FontLoadingError::NoFonts)
+    ));
+}
+
+#[test]
+fn test_font_runs() {
+    use crate::export::subset::Sfnt;
+
+    let data = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    let charmap = FontRef::from_index(data, 0).unwrap().charmap();
+    let be = |values: &[u16]| {
+        values
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<u8>>()
+    };
+
+    // a second font, which is Open Sans with 字 and 漢 mapped to the glyphs of a and b by a
+    // format 4 cmap, and nothing else
+    let codes = [0x5B57, 0x6F22, 0xFFFF];
+    let deltas = [charmap.map('a'), charmap.map('b'), 1]
+        .iter()
+        .zip(codes)
+        .map(|(glyph, code)| glyph.wrapping_sub(code))
+        .collect::<Vec<u16>>();
+    let cmap = [
+        be(&[0, 1, 3, 1, 0, 12]),
+        be(&[4, 16 + 8 * 3, 0, 6, 4, 1, 2]),
+        be(&codes),
+        be(&[0]),
+        be(&codes),
+        be(&deltas),
+        be(&[0; 3]),
+    ]
+    .concat();
+    let name = "HanTest".encode_utf16().collect::<Vec<u16>>();
+    let name = [
+        be(&[0, 1, 18, 3, 1, 0x409, 6, 2 * name.len() as u16, 0]),
+        be(&name),
+    ]
+    .concat();
+    let mut sfnt = Sfnt::parse(data, 0).unwrap();
+    sfnt.tables.insert(u32::from_be_bytes(*b"cmap"), cmap);
+    sfnt.tables.insert(u32::from_be_bytes(*b"name"), name);
+    let mut han_data = sfnt.write();
+
+    let font_set = FontSetBuilder::new()
+        .push(Font::from_collection(data, 0).unwrap())
+        .push(Font::from_data(&mut han_data).unwrap())
+        .build()
+        .unwrap();
+    let (open_sans, han) = (&font_set.inner[0], &font_set.inner[1]);
+    assert_eq!(han.name(), "HanTest");
+    assert!(han.covers('漢') && !han.covers('c') && !open_sans.covers('漢'));
+
+    let runs = |text, primary| {
+        font_set.font_runs(text, primary).map(|runs| {
+            runs.into_iter()
+                .map(|(r, f)| (r, f.name()))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(
+        runs("ab漢字cd", None),
+        Some(vec![
+            (0..2, "OpenSans-Regular"),
+            (2..8, "HanTest"),
+            (8..10, "OpenSans-Regular")
+        ])
+    );
+    // characters without a glyph in any font stay in the current run
+    assert_eq!(
+        runs("漢\u{E000}字c", Some(han)),
+        Some(vec![(0..9, "HanTest"), (9..10, "OpenSans-Regular")])
+    );
+    assert_eq!(runs("\u{E000}", None), None);
+}
+
+#[test]
+fn test_font_directory() {
+    use crate::export::subset::Sfnt;
+
+    let directory = std::env::temp_dir().join(format!("wordcloud-fonts-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    // indexed, as the names can be read, but not loadable without the horizontal metrics
+    let mut sfnt = Sfnt::parse(
+        include_bytes!("../../example/assets/OpenSans-Regular.ttf"),
+        0,
+    )
+    .unwrap();
+    sfnt.tables.remove(&u32::from_be_bytes(*b"hmtx"));
+    std::fs::write(directory.join("a-corrupt.ttf"), sfnt.write()).unwrap();
+
+    let mut buffers = vec![b"loaded before".to_vec()];
+    assert!(matches!(
+        FontSet::from_directory(&directory, &mut buffers),
+        Err(FontLoadingError::NoFonts)
+    ));
+
+    std::fs::copy(
+        "example/assets/OpenSans-Regular.ttf",
+        directory.join("b-valid.ttf"),
+    )
+    .unwrap();
+    let mut buffers = vec![b"loaded before".to_vec()];
+    let font_set = FontSet::from_directory(&directory, &mut buffers);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let font_set = font_set.unwrap();
+    assert_eq!(font_set.inner.len(), 1);
+    assert!(font_set.find("Open Sans", Weight::Regular).is_some());
+    drop(font_set);
+    assert_eq!(buffers.len(), 3);
+    assert_eq!(buffers[0], b"loaded before");
+}
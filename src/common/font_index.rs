@@ -0,0 +1,336 @@
+use std::fs::File;
+use std::io::{Error, Read};
+use std::path::{Path, PathBuf};
+
+use swash::{FontDataRef, FontRef, StringId};
+
+use crate::common::font::Font;
+use crate::io::RecursiveFolderIterator;
+
+/**
+    Directories, which are searched by [`FontIndex::system_fonts`]. Paths starting with `~` are
+    relative to the home directory.
+*/
+const SYSTEM_FONT_DIRECTORIES: [&str; 4] = [
+    "/usr/share/fonts",
+    "/usr/local/share/fonts",
+    "~/.local/share/fonts",
+    "~/.fonts",
+];
+
+/**
+    Weight of a font, from thin to black, as defined by CSS and OpenType
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Weight {
+    Thin,
+    ExtraLight,
+    Light,
+    #[default]
+    Regular,
+    Medium,
+    SemiBold,
+    Bold,
+    ExtraBold,
+    Black,
+}
+
+impl Weight {
+    const ALL: [Weight; 9] = [
+        Weight::Thin,
+        Weight::ExtraLight,
+        Weight::Light,
+        Weight::Regular,
+        Weight::Medium,
+        Weight::SemiBold,
+        Weight::Bold,
+        Weight::ExtraBold,
+        Weight::Black,
+    ];
+
+    /**
+        Numeric weight, `100` for [`Weight::Thin`] up to `900` for [`Weight::Black`]
+    */
+    pub fn value(&self) -> u16 {
+        (Weight::ALL.iter().position(|w| w == self).unwrap_or(3) as u16 + 1) * 100
+    }
+
+    /**
+        The named weight closest to the numeric `value`
+    */
+    pub fn from_value(value: u16) -> Self {
+        let index = (value.clamp(100, 900) + 50) / 100 - 1;
+        Weight::ALL[index as usize]
+    }
+}
+
+/**
+    Family, weight, style and width of a font, read from its `name` and `OS/2` tables
+*/
+#[derive(Clone, Debug)]
+pub(crate) struct FontDescription {
+    pub(crate) family: String,
+    pub(crate) weight: Weight,
+    pub(crate) italic: bool,
+    /// Whether the font is condensed or expanded
+    pub(crate) stretched: bool,
+    /// Whether the font is a face of a collection
+    pub(crate) collection: bool,
+}
+
+impl FontDescription {
+    pub(crate) fn of(re: &FontRef) -> Self {
+        let strings = re.localized_strings();
+        let family = strings
+            .find_by_id(StringId::TypographicFamily, Some("en"))
+            .or_else(|| strings.find_by_id(StringId::Family, Some("en")))
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let attributes = re.attributes();
+
+        FontDescription {
+            family,
+            weight: Weight::from_value(attributes.weight().0),
+            italic: attributes.style() != swash::Style::Normal,
+            stretched: attributes.stretch() != swash::Stretch::NORMAL,
+            collection: re.data.get(0..4) == Some(b"ttcf"),
+        }
+    }
+
+    /**
+        Reads the descriptions of the fonts in `data`, one for every face of a collection,
+        without loading the whole fonts. Data, which isn't a supported font, has none.
+    */
+    fn read(data: &mut Vec<u8>) -> Vec<Self> {
+        match data.get(0..4) {
+            Some(b"\x00\x01\x00\x00" | b"OTTO" | b"ttcf") => FontDataRef::new(data)
+                .map(|d| d.fonts().map(|re| Self::of(&re)).collect())
+                .unwrap_or_default(),
+            #[cfg(feature = "woff2")]
+            Some(b"wOF2") => Font::from_data(data)
+                .map(|f| Self::of(f.reference()))
+                .into_iter()
+                .collect(),
+            Some(b"wOFF") => Font::from_data(data)
+                .map(|f| Self::of(f.reference()))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /**
+        How far the font is from an upright font of normal width and `weight`, used to find the
+        best font of a family
+    */
+    pub(crate) fn distance(&self, weight: Weight) -> (bool, bool, u16) {
+        (
+            self.italic,
+            self.stretched,
+            self.weight.value().abs_diff(weight.value()),
+        )
+    }
+
+    /**
+        Whether the family is `family`, ignoring case
+    */
+    pub(crate) fn is_family(&self, family: &str) -> bool {
+        self.family.eq_ignore_ascii_case(family.trim())
+    }
+}
+
+/**
+    A font file found by a [`FontIndex`]
+*/
+#[derive(Clone, Debug)]
+pub struct FontEntry {
+    path: PathBuf,
+    index: usize,
+    description: FontDescription,
+}
+
+impl FontEntry {
+    /**
+        Path of the font file
+    */
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /**
+        Index of the face in a font collection, `0` for single fonts
+    */
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /**
+        Family name of the font, e.g. `DejaVu Sans`
+    */
+    pub fn family(&self) -> &str {
+        &self.description.family
+    }
+
+    pub fn weight(&self) -> Weight {
+        self.description.weight
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.description.italic
+    }
+
+    /**
+        Whether the font is part of a collection, which is loaded with [`Font::from_collection`]
+        and [`Self::index`]
+    */
+    pub fn is_collection(&self) -> bool {
+        self.description.collection
+    }
+
+    /**
+        Reads the font file, the result can be loaded with [`Font::from_data`], or
+        [`Font::from_collection`] for collections
+    */
+    pub fn read(&self) -> Result<Vec<u8>, Error> {
+        read_file(&self.path)
+    }
+}
+
+/**
+    Index of the font files in one or more directories, which can be searched by family and
+    weight. Only the names of the fonts are kept in memory, the fonts themselves are read when
+    they are needed.
+
+    ```
+    use wordcloud::font::{Font, FontIndex, FontSetBuilder, Weight};
+
+    let index = FontIndex::system_fonts();
+    let entry = index.find("DejaVu Sans", Weight::Bold).expect("font not installed");
+
+    let data = entry.read().unwrap();
+    let font_set = FontSetBuilder::new()
+        .push(Font::from_collection(&data, entry.index()).unwrap())
+        .build()
+        .unwrap();
+    ```
+*/
+#[derive(Clone, Debug, Default)]
+pub struct FontIndex {
+    entries: Vec<FontEntry>,
+}
+
+impl FontIndex {
+    /**
+        Indexes all `.ttf`, `.otf`, `.woff` and `.woff2` files and the faces of all `.ttc` and
+        `.otc` collections in `directory` and its subdirectories. Files, which can't be read or
+        aren't fonts, are skipped.
+    */
+    pub fn from_directory<P: AsRef<Path>>(directory: P) -> Result<Self, Error> {
+        let mut index = FontIndex::default();
+        index.add_directory(directory)?;
+        Ok(index)
+    }
+
+    /**
+        Indexes the standard font directories of Linux: `/usr/share/fonts`,
+        `/usr/local/share/fonts`, `~/.local/share/fonts` and `~/.fonts`. Missing directories are
+        skipped. This reads every installed font once, so it may take a moment.
+    */
+    pub fn system_fonts() -> Self {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let mut index = FontIndex::default();
+        for directory in SYSTEM_FONT_DIRECTORIES {
+            let path = match (directory.strip_prefix("~/"), &home) {
+                (Some(relative), Some(home)) => home.join(relative),
+                (Some(_), None) => continue,
+                (None, _) => PathBuf::from(directory),
+            };
+            let _ = index.add_directory(path);
+        }
+        index
+    }
+
+    /**
+        Adds the fonts in `directory` to the index, see [`Self::from_directory`]
+    */
+    pub fn add_directory<P: AsRef<Path>>(&mut self, directory: P) -> Result<(), Error> {
+        let mut paths =
+            RecursiveFolderIterator::new(directory.as_ref(), is_font_file)?.collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            if self.entries.iter().any(|e| e.path == path) {
+                continue;
+            }
+            let mut data = match read_file(&path) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            for (index, description) in FontDescription::read(&mut data).into_iter().enumerate() {
+                self.entries.push(FontEntry {
+                    path: path.clone(),
+                    index,
+                    description,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /**
+        All indexed fonts, sorted by path within each directory
+    */
+    pub fn entries(&self) -> &[FontEntry] {
+        &self.entries
+    }
+
+    /**
+        Names of all indexed families, without duplicates
+    */
+    pub fn families(&self) -> Vec<&str> {
+        let mut families: Vec<&str> = self.entries.iter().map(|e| e.family()).collect();
+        families.sort_unstable();
+        families.dedup();
+        families
+    }
+
+    /**
+        Finds the font of `family` with the weight closest to `weight`, preferring upright
+        fonts of normal width. The family name is compared ignoring case.
+    */
+    pub fn find(&self, family: &str, weight: Weight) -> Option<&FontEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.description.is_family(family))
+            .min_by_key(|e| e.description.distance(weight))
+    }
+}
+
+fn is_font_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+        ["ttf", "otf", "ttc", "otc", "woff", "woff2"]
+            .iter()
+            .any(|f| e.eq_ignore_ascii_case(f))
+    })
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[test]
+fn test_font_index() {
+    let index = FontIndex::from_directory("example/assets").unwrap();
+    assert_eq!(index.families(), vec!["Open Sans"]);
+
+    // the closest weight is chosen
+    let entry = index.find("open sans", Weight::Bold).unwrap();
+    assert_eq!(entry.weight(), Weight::Regular);
+    assert!(entry.path().ends_with("OpenSans-Regular.ttf"));
+    assert!(index.find("Noto Sans", Weight::Regular).is_none());
+
+    assert_eq!(Weight::from_value(650), Weight::Bold);
+    assert_eq!(Weight::SemiBold.value(), 600);
+}
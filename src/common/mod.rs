@@ -1,5 +1,11 @@
+pub(crate) mod bidi;
+/**
+Provides the colors used for the words.
+ */
+pub mod color;
 /**
 Provides everything regarding the used fonts.
  */
 pub mod font;
-pub(crate) mod svg_command;
\ No newline at end of file
+pub(crate) mod font_index;
+pub(crate) mod svg_command;
@@ -1,7 +1,7 @@
 use crate::types::point::Point;
 use itertools::Itertools;
 use std::f32::consts::PI;
-use std::ops::{Add, Deref, Mul, Sub};
+use std::ops::{Add, Mul, Sub};
 
 #[derive(Debug, Clone)]
 pub(crate) enum SVGPathCommand {
@@ -12,39 +12,217 @@ pub(crate) enum SVGPathCommand {
     End(End),
 }
 
-macro_rules! format_float {
-    ($num:expr) => {
-        format!("{:.2}", $num).deref()
-    };
+impl SVGPathCommand {
+    pub(crate) fn encode(&self, offset: &Point<f32>, encoder: &mut PathEncoder) {
+        match self {
+            SVGPathCommand::Move(m) => encoder.move_to(m.position + *offset),
+            SVGPathCommand::Line(l) => encoder.line_to(l.end + *offset),
+            SVGPathCommand::QuadCurve(q) => encoder.quad_to(q.c1 + *offset, q.e + *offset),
+            SVGPathCommand::Curve(c) => {
+                encoder.curve_to(c.c2 + *offset, c.c1 + *offset, c.e + *offset)
+            }
+            SVGPathCommand::End(_) => encoder.close(),
+        }
+    }
 }
 
-pub(crate) trait SvgCommand {
-    fn append_to_string(&self, offset: &Point<f32>, string: &mut String);
-    fn length_estimation(&self) -> usize;
+/**
+    Writes path commands as compact SVG path data. Apart from the first move, which is absolute,
+    all commands are relative and the coordinates are rounded to `precision` decimal places.
+    Moves which aren't followed by a segment and segments which have no length after rounding
+    are left out.
+
+    The positions are tracked on the rounded grid, so the relative commands don't accumulate
+    rounding errors. As every encoder starts with an absolute move, the data of several encoders
+    can be concatenated into one path.
+*/
+pub(crate) struct PathEncoder {
+    precision: usize,
+    scale: f64,
+    d: String,
+    current: Option<(i64, i64)>,
+    subpath_start: (i64, i64),
+    pending_move: Option<(i64, i64)>,
+    subpath_open: bool,
+    last_command: Option<char>,
+    last_number_has_dot: Option<bool>,
 }
 
-impl SVGPathCommand {
-    pub(crate) fn append_to_string(&self, offset: &Point<f32>, string: &mut String) {
-        match self {
-            SVGPathCommand::Move(x) => x.append_to_string(offset, string),
-            SVGPathCommand::Line(x) => x.append_to_string(offset, string),
-            SVGPathCommand::QuadCurve(x) => x.append_to_string(offset, string),
-            SVGPathCommand::Curve(x) => x.append_to_string(offset, string),
-            SVGPathCommand::End(x) => x.append_to_string(offset, string),
+impl PathEncoder {
+    pub(crate) fn new(precision: usize) -> Self {
+        let precision = precision.min(8);
+        PathEncoder {
+            precision,
+            scale: 10f64.powi(precision as i32),
+            d: String::new(),
+            current: None,
+            subpath_start: (0, 0),
+            pending_move: None,
+            subpath_open: false,
+            last_command: None,
+            last_number_has_dot: None,
         }
     }
 
-    pub(crate) fn length_estimation(&self) -> usize {
-        match self {
-            SVGPathCommand::Move(x) => x.length_estimation(),
-            SVGPathCommand::Line(x) => x.length_estimation(),
-            SVGPathCommand::QuadCurve(x) => x.length_estimation(),
-            SVGPathCommand::Curve(x) => x.length_estimation(),
-            SVGPathCommand::End(x) => x.length_estimation(),
+    fn round(&self, point: Point<f32>) -> (i64, i64) {
+        (
+            (point.x as f64 * self.scale).round() as i64,
+            (point.y as f64 * self.scale).round() as i64,
+        )
+    }
+
+    /**
+        The point the next segment starts at
+    */
+    fn reference(&self) -> (i64, i64) {
+        self.pending_move.or(self.current).unwrap_or((0, 0))
+    }
+
+    pub(crate) fn move_to(&mut self, position: Point<f32>) {
+        self.pending_move = Some(self.round(position));
+    }
+
+    pub(crate) fn line_to(&mut self, end: Point<f32>) {
+        let (x, y) = self.reference();
+        let end = self.round(end);
+        let (dx, dy) = (end.0 - x, end.1 - y);
+
+        match (dx, dy) {
+            (0, 0) => return,
+            (dx, 0) => self.segment('h', &[dx]),
+            (0, dy) => self.segment('v', &[dy]),
+            (dx, dy) => self.segment('l', &[dx, dy]),
+        }
+        self.current = Some(end);
+    }
+
+    pub(crate) fn quad_to(&mut self, control: Point<f32>, end: Point<f32>) {
+        let (x, y) = self.reference();
+        let (control, end) = (self.round(control), self.round(end));
+        if control == (x, y) && end == (x, y) {
+            return;
+        }
+
+        self.segment('q', &[control.0 - x, control.1 - y, end.0 - x, end.1 - y]);
+        self.current = Some(end);
+    }
+
+    pub(crate) fn curve_to(&mut self, control1: Point<f32>, control2: Point<f32>, end: Point<f32>) {
+        let (x, y) = self.reference();
+        let (control1, control2, end) =
+            (self.round(control1), self.round(control2), self.round(end));
+        if control1 == (x, y) && control2 == (x, y) && end == (x, y) {
+            return;
+        }
+
+        self.segment(
+            'c',
+            &[
+                control1.0 - x,
+                control1.1 - y,
+                control2.0 - x,
+                control2.1 - y,
+                end.0 - x,
+                end.1 - y,
+            ],
+        );
+        self.current = Some(end);
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.pending_move = None;
+        if self.subpath_open {
+            self.command('z', &[]);
+            self.current = Some(self.subpath_start);
+            self.subpath_open = false;
+        }
+    }
+
+    /**
+        Writes a segment, preceded by the pending move. A move to the start of the subpath
+        which was just closed is redundant, because that is where the next subpath starts anyway.
+    */
+    fn segment(&mut self, command: char, numbers: &[i64]) {
+        let position = self.current;
+        if let Some(target) = self.pending_move.take() {
+            match position {
+                None => self.command('M', &[target.0, target.1]),
+                Some(p) if self.subpath_open || p != target => {
+                    self.command('m', &[target.0 - p.0, target.1 - p.1]);
+                    // coordinates following a relative move are relative lines
+                    self.last_command = Some('l');
+                }
+                Some(_) => {}
+            }
+            self.current = Some(target);
+            self.subpath_start = target;
+            self.subpath_open = false;
+        } else if position.is_none() {
+            self.command('M', &[0, 0]);
+            self.current = Some((0, 0));
+        }
+        if !self.subpath_open {
+            self.subpath_start = self.current.unwrap_or((0, 0));
+            self.subpath_open = true;
         }
+
+        self.command(command, numbers);
+    }
+
+    /**
+        Writes a command, the letter is left out if it repeats the previous command. Numbers are
+        only separated if necessary.
+    */
+    fn command(&mut self, command: char, numbers: &[i64]) {
+        if self.last_command != Some(command) || numbers.is_empty() {
+            self.d.push(command);
+            self.last_command = Some(command);
+            self.last_number_has_dot = None;
+        }
+
+        for number in numbers {
+            let formatted = format_fixed(*number, self.precision);
+            let unambiguous = formatted.starts_with('-')
+                || (formatted.starts_with('.') && self.last_number_has_dot == Some(true));
+            if self.last_number_has_dot.is_some() && !unambiguous {
+                self.d.push(' ');
+            }
+            self.last_number_has_dot = Some(formatted.contains('.'));
+            self.d.push_str(&formatted);
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.d
     }
 }
 
+/**
+    Formats `value` / 10^`precision` without trailing zeros and without the leading zero of
+    numbers between -1 and 1.
+*/
+fn format_fixed(value: i64, precision: usize) -> String {
+    let divisor = 10u64.pow(precision as u32);
+    let (integer, fraction) = (
+        value.unsigned_abs() / divisor,
+        value.unsigned_abs() % divisor,
+    );
+
+    let mut formatted = String::new();
+    if value < 0 {
+        formatted.push('-');
+    }
+    if integer != 0 || fraction == 0 {
+        formatted.push_str(&integer.to_string());
+    }
+    if fraction != 0 {
+        let digits = format!("{:0width$}", fraction, width = precision);
+        formatted.push('.');
+        formatted.push_str(digits.trim_end_matches('0'));
+    }
+    formatted
+}
+
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Line<T> {
     pub(crate) start: Point<T>,
@@ -213,81 +391,6 @@ where
     }
 }
 
-impl SvgCommand for Line<f32> {
-    fn append_to_string(&self, offset: &Point<f32>, string: &mut String) {
-        string.push_str("L ");
-        string.push_str(format_float!(self.end.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.end.y + offset.y))
-    }
-
-    fn length_estimation(&self) -> usize {
-        3 + 7 + 7
-    }
-}
-
-impl SvgCommand for Move {
-    fn append_to_string(&self, offset: &Point<f32>, string: &mut String) {
-        string.push_str("M ");
-        string.push_str(format_float!(self.position.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.position.y + offset.y))
-    }
-
-    fn length_estimation(&self) -> usize {
-        3 + 7 + 7
-    }
-}
-
-impl SvgCommand for QuadCurve {
-    fn append_to_string(&self, offset: &Point<f32>, string: &mut String) {
-        string.push_str("Q ");
-        string.push_str(format_float!(self.c1.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.c1.y + offset.y));
-        string.push(',');
-        string.push_str(format_float!(self.e.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.e.y + offset.y))
-    }
-
-    fn length_estimation(&self) -> usize {
-        3 + 7 + 7 + 3 + 7 + 7
-    }
-}
-
-impl SvgCommand for Curve {
-    fn append_to_string(&self, offset: &Point<f32>, string: &mut String) {
-        string.push_str("C ");
-        string.push_str(format_float!(self.c2.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.c2.y + offset.y));
-        string.push(',');
-        string.push_str(format_float!(self.c1.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.c1.y + offset.y));
-        string.push(',');
-        string.push_str(format_float!(self.e.x + offset.x));
-        string.push(' ');
-        string.push_str(format_float!(self.e.y + offset.y));
-        string.push(',');
-    }
-
-    fn length_estimation(&self) -> usize {
-        2 + 6 + 7 + 7 + 7 + 7 + 7 + 7
-    }
-}
-
-impl SvgCommand for End {
-    fn append_to_string(&self, _offset: &Point<f32>, string: &mut String) {
-        string.push('Z');
-    }
-
-    fn length_estimation(&self) -> usize {
-        1
-    }
-}
-
 impl QuadCurve {
     fn divide_quad(&self, center_points: usize) -> Vec<Point<f32>> {
         let points = center_points + 2;
@@ -306,6 +409,22 @@ impl QuadCurve {
             .collect()
     }
 
+    /**
+        Like [`Self::approximate`], but uses as many lines as needed to stay within `tolerance`
+        of the curve.
+    */
+    pub(crate) fn approximate_with_tolerance(&self, tolerance: f32) -> Vec<Line<f32>> {
+        // Wang's formula for the number of segments of a quadratic curve
+        let deviation = (self.s - self.c1 * 2. + self.e).length();
+        let segments = segment_count(deviation / 4., tolerance);
+
+        self.divide_quad(segments - 1)
+            .iter()
+            .tuple_windows()
+            .map(|(s, e)| Line { start: *s, end: *e })
+            .collect()
+    }
+
     /// See https://www.geogebra.org/m/YGqtDGzK
     fn get_point_on_curve(&self, t: f32) -> Point<f32> {
         // F = D + t(E - D)
@@ -337,11 +456,30 @@ impl Curve {
             .collect()
     }
 
+    /**
+        Like [`Self::approximate`], but uses as many lines as needed to stay within `tolerance`
+        of the curve.
+    */
+    pub(crate) fn approximate_with_tolerance(&self, tolerance: f32) -> Vec<Line<f32>> {
+        // Wang's formula for the number of segments of a cubic curve
+        let deviation = f32::max(
+            (self.s - self.c2 * 2. + self.c1).length(),
+            (self.c2 - self.c1 * 2. + self.e).length(),
+        );
+        let segments = segment_count(deviation * 0.75, tolerance);
+
+        self.divide_curve(segments - 1)
+            .iter()
+            .tuple_windows()
+            .map(|(s, e)| Line { start: *s, end: *e })
+            .collect()
+    }
+
     fn get_point_on_curve(&self, t: f32) -> Point<f32> {
         /*
         p1 = s
-        p2 = c1
-        p3 = c2
+        p2 = c2 (the first control point)
+        p3 = c1 (the second control point)
         p4 = e
 
         P_5 = (1-t) P_1 + t P_2
@@ -353,12 +491,53 @@ impl Curve {
         */
 
         let inv_t = 1. - t;
-        let p5 = self.s * inv_t + self.c1 * t;
-        let p6 = self.c1 * inv_t + self.c2 * t;
-        let p7 = self.c2 * inv_t + self.e * t;
+        let p5 = self.s * inv_t + self.c2 * t;
+        let p6 = self.c2 * inv_t + self.c1 * t;
+        let p7 = self.c1 * inv_t + self.e * t;
         let p8 = p5 * inv_t + p6 * t;
         let p9 = p6 * inv_t + p7 * t;
 
         p8 * inv_t + p9 * t
     }
 }
+
+fn segment_count(deviation: f32, tolerance: f32) -> usize {
+    let tolerance = f32::max(tolerance, 0.001);
+    ((deviation / tolerance).sqrt().ceil() as usize).clamp(1, 128)
+}
+
+#[test]
+fn test_path_encoder() {
+    let mut encoder = PathEncoder::new(2);
+    encoder.move_to(Point { x: 10., y: 10. });
+    encoder.move_to(Point { x: 20., y: 20. });
+    encoder.line_to(Point { x: 30., y: 20. });
+    encoder.line_to(Point { x: 30.001, y: 20. });
+    encoder.line_to(Point { x: 30., y: 25. });
+    encoder.close();
+    encoder.move_to(Point { x: 20., y: 20. });
+    encoder.line_to(Point { x: 20.5, y: 19. });
+    encoder.quad_to(Point { x: 20.75, y: 19.5 }, Point { x: 21., y: 19. });
+    encoder.close();
+    encoder.move_to(Point { x: 0., y: 0. });
+    encoder.close();
+
+    assert_eq!(encoder.finish(), "M20 20h10v5zl.5-1q.25.5.5 0z");
+}
+
+#[test]
+fn test_curve_control_points() {
+    // the outline builder stores the first control point of a cubic curve in `c2`
+    let curve = Curve {
+        s: Point { x: 0., y: 0. },
+        c2: Point { x: 0., y: 27. },
+        c1: Point { x: 27., y: 27. },
+        e: Point { x: 27., y: 0. },
+    };
+    let points = curve.divide_curve(2);
+
+    assert_eq!(points.len(), 4);
+    assert!((points[1].x - 7.).abs() < 0.001);
+    assert!((points[1].y - 18.).abs() < 0.001);
+    assert!((points[2].x - 20.).abs() < 0.001);
+}
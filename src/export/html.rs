@@ -0,0 +1,232 @@
+use std::io::{Error, Write};
+
+use svg::node::element::Anchor;
+use svg::Node;
+
+use crate::cloud::style::PlacedWordInfo;
+use crate::export::svg_writer::SvgWriter;
+use crate::export::{escape_xml, ExportOptions};
+use crate::WordCloud;
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 1em; }
+#wordcloud-search { font-size: 1em; padding: 0.3em 0.5em; margin-bottom: 1em; width: 20em; max-width: 100%; }
+#wordcloud svg { max-width: 100%; height: auto; }
+#wordcloud-tooltip { position: absolute; pointer-events: none; padding: 0.2em 0.5em; border-radius: 3px; background: rgba(0, 0, 0, 0.8); color: #fff; }
+.wordcloud-word { transition: opacity 0.2s; }
+.wordcloud-word:hover { opacity: 0.7; }
+a .wordcloud-word { cursor: pointer; }
+svg.searching .wordcloud-word { opacity: 0.15; }
+svg.searching .wordcloud-word.match { opacity: 1; paint-order: stroke; stroke: #ffd54f; stroke-width: 6px; }";
+
+const TOOLTIP_SCRIPT: &str = "const cloud = document.querySelector(\"#wordcloud svg\");
+const tooltip = document.getElementById(\"wordcloud-tooltip\");
+for (const word of cloud.querySelectorAll(\".wordcloud-word\")) {
+    word.addEventListener(\"mousemove\", (event) => {
+        tooltip.textContent = `${word.dataset.word}: ${word.dataset.count}`;
+        tooltip.style.left = `${event.pageX + 12}px`;
+        tooltip.style.top = `${event.pageY + 12}px`;
+        tooltip.hidden = false;
+    });
+    word.addEventListener(\"mouseleave\", () => tooltip.hidden = true);
+}";
+
+const SEARCH_SCRIPT: &str = "const search = document.getElementById(\"wordcloud-search\");
+search.addEventListener(\"input\", () => {
+    const query = search.value.trim().toLowerCase();
+    cloud.classList.toggle(\"searching\", query.length > 0);
+    for (const word of cloud.querySelectorAll(\".wordcloud-word\")) {
+        word.classList.toggle(\"match\", query.length > 0 && word.dataset.word.toLowerCase().includes(query));
+    }
+});";
+
+type LinkFn<'l> = Box<dyn Fn(&PlacedWordInfo) -> Option<String> + 'l>;
+
+/**
+    Options used by [`crate::WordCloud::export_html_with`].
+
+    ```no_run
+    use wordcloud::{ExportOptions, HtmlOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = HtmlOptions::new()
+        .title("Customer feedback")
+        .link(|info| Some(format!("https://example.com/search?q={}", info.text)))
+        .export_options(ExportOptions::new().margin(10.));
+
+    let html = wc.export_html_with(&options).unwrap();
+    ```
+*/
+pub struct HtmlOptions<'l> {
+    title: String,
+    search: bool,
+    link: Option<LinkFn<'l>>,
+    export_options: ExportOptions,
+}
+
+impl<'l> Default for HtmlOptions<'l> {
+    fn default() -> Self {
+        HtmlOptions {
+            title: String::from("WordCloud"),
+            search: true,
+            link: None,
+            export_options: ExportOptions::default(),
+        }
+    }
+}
+
+impl<'l> HtmlOptions<'l> {
+    /**
+        Construct the default [`HtmlOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Title of the page, defaults to `WordCloud`
+    */
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = String::from(title);
+        self
+    }
+
+    /**
+        Whether a search box, which highlights the matching words, is shown above the
+        WordCloud. Defaults to `true`.
+    */
+    pub fn search(mut self, search: bool) -> Self {
+        self.search = search;
+        self
+    }
+
+    /**
+        Turns words into hyperlinks to the URL returned by `link`. Words for which it returns
+        `None` are not linked.
+    */
+    pub fn link(mut self, link: impl Fn(&PlacedWordInfo) -> Option<String> + 'l) -> Self {
+        self.link = Some(Box::new(link));
+        self
+    }
+
+    /**
+        [`ExportOptions`] used for the embedded SVG
+    */
+    pub fn export_options(mut self, export_options: ExportOptions) -> Self {
+        self.export_options = export_options;
+        self
+    }
+}
+
+impl<'a> WordCloud<'a> {
+    /**
+        Export the resulting WordCloud as a self-contained HTML page. The WordCloud is embedded
+        as SVG with text elements like in [`Self::export_text`]. Hovering a word shows its count,
+        a search box highlights the matching words.
+    */
+    pub fn export_html(&self) -> Result<String, Error> {
+        self.export_html_with(&HtmlOptions::default())
+    }
+
+    /**
+        Like [`Self::export_html`], but customized with [`HtmlOptions`].
+    */
+    pub fn export_html_with(&self, options: &HtmlOptions) -> Result<String, Error> {
+        let mut target = Vec::new();
+        write!(
+            target,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+            escape_xml(&options.title),
+            HTML_STYLE
+        )?;
+        if options.search {
+            target.write_all(
+                b"<input type=\"search\" id=\"wordcloud-search\" placeholder=\"Search words\">\n",
+            )?;
+        }
+        target.write_all(b"<div id=\"wordcloud\">\n")?;
+
+        let mut writer = SvgWriter::new(target, options.export_options.formatting);
+        self.write_text_svg(&mut writer, &options.export_options, |info, mut t| {
+            let mut class = String::from("wordcloud-word");
+            if let Some(style_class) = t.get_attributes().get("class") {
+                class.push(' ');
+                class.push_str(style_class);
+            }
+            t.assign("class", class);
+            t.assign("data-word", escape_xml(info.text));
+            t.assign("data-count", info.count);
+            t.assign("data-rank", info.rank);
+
+            match options.link.as_ref().and_then(|link| link(info)) {
+                None => Box::new(t),
+                Some(url) => Box::new(Anchor::new().set("href", escape_xml(&url)).add(t)),
+            }
+        })?;
+
+        let mut target = writer.finish()?;
+        target.write_all(b"\n</div>\n<div id=\"wordcloud-tooltip\" hidden></div>\n<script>\n")?;
+        target.write_all(TOOLTIP_SCRIPT.as_bytes())?;
+        if options.search {
+            write!(target, "\n{}", SEARCH_SCRIPT)?;
+        }
+        target.write_all(b"\n</script>\n</body>\n</html>\n")?;
+
+        Ok(String::from_utf8(target).expect("decoding the written string failed"))
+    }
+
+    /**
+        Writes the result of [`Self::export_html`] to a file.
+    */
+    pub fn export_html_to_file(&self, filename: &str) -> Result<(), Error> {
+        let html = self.export_html()?;
+        std::fs::write(filename, html.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_html_export() {
+    use crate::cloud::word_cloud::{test_font_set, test_words};
+    use crate::{Dimensions, WordCloudBuilder};
+
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(600, 600))
+        .font(&font_set)
+        .build()
+        .unwrap();
+    wc.write_content(test_words(&[("linked", 12), ("<b>&\"", 6)]), 10)
+        .unwrap();
+
+    let html = wc.export_html().unwrap();
+    assert!(html.contains(r#"data-word="linked""#));
+    assert!(html.contains(r#"data-count="12""#));
+    assert!(html.contains(r#"data-rank="0""#));
+    assert!(html.contains(r#"data-rank="1""#));
+    assert!(html.contains(r#"data-word="&lt;b&gt;&amp;&quot;""#));
+    assert!(!html.contains("<b>"));
+    assert!(!html.contains("<a "));
+    assert!(html.contains(r#"id="wordcloud-search""#));
+
+    let html = wc
+        .export_html_with(
+            &HtmlOptions::new()
+                .title("<Feedback>")
+                .search(false)
+                .link(|info| {
+                    (info.rank == 0).then(|| format!("https://example.com/?q={}&x=\"", info.text))
+                }),
+        )
+        .unwrap();
+    assert!(html.contains("<title>&lt;Feedback&gt;</title>"));
+    assert!(!html.contains(r#"id="wordcloud-search""#));
+    assert_eq!(html.matches("<a ").count(), 1);
+    assert!(html.contains(r#"href="https://example.com/?q=linked&amp;x=&quot;""#));
+    let link = html.find("<a ").unwrap();
+    assert!(html[link..html.find("</a>").unwrap()].contains(r#"data-word="linked""#));
+}
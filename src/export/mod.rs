@@ -0,0 +1,513 @@
+use std::io::{Error, Write};
+
+use svg::node::element::{Definitions, LinearGradient, RadialGradient, Rectangle, Stop};
+use svg::Node;
+
+use crate::cloud::style::PlacedWordInfo;
+use crate::color::Color;
+use crate::export::svg_writer::SvgWriter;
+use crate::types::rect::Rect;
+
+pub(crate) mod html;
+pub(crate) mod pdf;
+pub(crate) mod plotter;
+#[cfg(feature = "raster")]
+pub(crate) mod raster;
+pub(crate) mod subset;
+pub(crate) mod svg_writer;
+pub(crate) mod tiles;
+
+/**
+    Fill of the canvas behind the words.
+*/
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Background {
+    /// No background, the SVG stays transparent.
+    #[default]
+    None,
+    /// A single color.
+    Color(Color),
+    /// A linear gradient from `from` to `to`. The `angle` is in degrees, `0` runs from left to
+    /// right, `90` from top to bottom.
+    LinearGradient { from: Color, to: Color, angle: f32 },
+    /// A radial gradient from `inner` in the center to `outer` at the edges.
+    RadialGradient { inner: Color, outer: Color },
+}
+
+impl Background {
+    const GRADIENT_ID: &'static str = "wordcloud-background";
+
+    fn stop(offset: &str, color: &Color) -> Stop {
+        let mut stop = Stop::new()
+            .set("offset", offset)
+            .set("stop-color", color.to_string());
+        if color.a != 255 {
+            stop.assign("stop-opacity", format_number(color.a as f32 / 255., 3));
+        }
+        stop
+    }
+
+    /**
+        Writes the background covering `area`, including the definition of its gradient
+    */
+    pub(crate) fn write_to<W: Write>(
+        &self,
+        writer: &mut SvgWriter<W>,
+        area: Rect<f32>,
+        options: &ExportOptions,
+    ) -> Result<(), Error> {
+        let fill = match self {
+            Background::None => return Ok(()),
+            Background::Color(c) => c.to_string(),
+            Background::LinearGradient { from, to, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let gradient = LinearGradient::new()
+                    .set("id", Background::GRADIENT_ID)
+                    .set("x1", options.number(0.5 - cos / 2.))
+                    .set("y1", options.number(0.5 - sin / 2.))
+                    .set("x2", options.number(0.5 + cos / 2.))
+                    .set("y2", options.number(0.5 + sin / 2.))
+                    .add(Background::stop("0", from))
+                    .add(Background::stop("1", to));
+                writer.node(&Definitions::new().add(gradient))?;
+                format!("url(#{})", Background::GRADIENT_ID)
+            }
+            Background::RadialGradient { inner, outer } => {
+                let gradient = RadialGradient::new()
+                    .set("id", Background::GRADIENT_ID)
+                    .add(Background::stop("0", inner))
+                    .add(Background::stop("1", outer));
+                writer.node(&Definitions::new().add(gradient))?;
+                format!("url(#{})", Background::GRADIENT_ID)
+            }
+        };
+
+        let mut rect = Rectangle::new()
+            .set("x", options.number(area.min.x))
+            .set("y", options.number(area.min.y))
+            .set("width", options.number(area.width()))
+            .set("height", options.number(area.height()))
+            .set("fill", fill);
+        if let Background::Color(c) = self {
+            if c.a != 255 {
+                rect.assign("fill-opacity", format_number(c.a as f32 / 255., 3));
+            }
+        }
+
+        writer.node(&rect)
+    }
+}
+
+/**
+    Layout of the exported XML.
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum XmlFormatting {
+    /// Everything on a single line.
+    Compact,
+    /// Every element on its own line.
+    #[default]
+    Newlines,
+    /// Every element on its own line, indented by the given number of spaces per level.
+    Indented(usize),
+}
+
+/**
+    Options shared by all SVG based exports, used directly by
+    [`crate::WordCloud::export_text_with`]. The other exports take their own options, which
+    contain the [`ExportOptions`], like [`RenderedOptions`].
+
+    ```no_run
+    use wordcloud::{Background, ExportOptions, XmlFormatting};
+    use wordcloud::color::Color;
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = ExportOptions::new()
+        .background(Background::Color(Color::rgb(255, 255, 255)))
+        .margin(20.)
+        .precision(1)
+        .formatting(XmlFormatting::Indented(2));
+
+    let svg = wc.export_text_with(&options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    pub(crate) background: Background,
+    pub(crate) margin: f32,
+    pub(crate) precision: usize,
+    pub(crate) embed_fonts: bool,
+    pub(crate) subset_fonts: bool,
+    #[cfg(feature = "woff2")]
+    pub(crate) compress_fonts: bool,
+    pub(crate) formatting: XmlFormatting,
+    pub(crate) word_ids: Option<String>,
+    pub(crate) word_class: Option<String>,
+    pub(crate) word_data: bool,
+    pub(crate) style_sheet: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) description: Option<String>,
+    pub(crate) compatible: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            background: Background::None,
+            margin: 0.,
+            precision: 2,
+            embed_fonts: true,
+            subset_fonts: true,
+            #[cfg(feature = "woff2")]
+            compress_fonts: false,
+            formatting: XmlFormatting::Newlines,
+            word_ids: None,
+            word_class: None,
+            word_data: false,
+            style_sheet: None,
+            title: None,
+            description: None,
+            compatible: false,
+        }
+    }
+}
+
+impl ExportOptions {
+    /**
+        Construct the default [`ExportOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        [`Background`] of the canvas, transparent by default
+    */
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /**
+        Empty space in pixels added around the canvas on every side
+    */
+    pub fn margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /**
+        Maximum number of decimal places of the exported numbers, defaults to `2`
+    */
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /**
+        Whether [`crate::WordCloud::export_text_with`] embeds the used fonts. Without them, the
+        fonts have to be installed wherever the SVG is displayed. Defaults to `true`.
+    */
+    pub fn embed_fonts(mut self, embed_fonts: bool) -> Self {
+        self.embed_fonts = embed_fonts;
+        self
+    }
+
+    /**
+        Whether the embedded fonts only contain the glyphs used in the WordCloud, which keeps
+        the exports small, especially with large fonts. Only fonts with TrueType or CFF outlines
+        are subset, other fonts are embedded completely. Defaults to `true`.
+    */
+    pub fn subset_fonts(mut self, subset_fonts: bool) -> Self {
+        self.subset_fonts = subset_fonts;
+        self
+    }
+
+    /**
+        Whether the subset fonts embedded by [`crate::WordCloud::export_text_with`] are
+        compressed as WOFF2. Slower, but makes the SVG even smaller. Defaults to `false`.
+    */
+    #[cfg(feature = "woff2")]
+    pub fn compress_fonts(mut self, compress_fonts: bool) -> Self {
+        self.compress_fonts = compress_fonts;
+        self
+    }
+
+    /**
+        [`XmlFormatting`] of the output, defaults to [`XmlFormatting::Newlines`]
+    */
+    pub fn formatting(mut self, formatting: XmlFormatting) -> Self {
+        self.formatting = formatting;
+        self
+    }
+
+    /**
+        Gives every word element the `id` `prefix` followed by its position in rank order,
+        e.g. `word-0` for the most common word with the prefix `word-`.
+    */
+    pub fn word_ids(mut self, prefix: &str) -> Self {
+        self.word_ids = Some(String::from(prefix));
+        self
+    }
+
+    /**
+        CSS class added to every word element, in addition to the class of the
+        [`crate::WordStyle`].
+    */
+    pub fn word_class(mut self, class: &str) -> Self {
+        self.word_class = Some(String::from(class));
+        self
+    }
+
+    /**
+        Whether every word element gets the attributes `data-word`, `data-count`, `data-rank`,
+        `data-font` and `data-rotation`, which can be used by scripts and CSS selectors.
+        Defaults to `false`.
+    */
+    pub fn word_data(mut self, word_data: bool) -> Self {
+        self.word_data = word_data;
+        self
+    }
+
+    /**
+        CSS written as `<style>` element into the SVG, before the words. The CSS is inserted
+        as is, so it must not contain `<` or `&`.
+    */
+    pub fn style_sheet(mut self, css: &str) -> Self {
+        self.style_sheet = Some(String::from(css));
+        self
+    }
+
+    /**
+        Title of the SVG, which is read by screen readers. Setting it gives the document the
+        role `img`, labelled by the title.
+    */
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(String::from(title));
+        self
+    }
+
+    /**
+        Longer description of the SVG for screen readers, like [`Self::title`]
+    */
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(String::from(description));
+        self
+    }
+
+    /**
+        Whether the SVG avoids CSS features, which renderers like resvg, librsvg or Inkscape and
+        many PDF converters don't support. Rotated words in [`crate::WordCloud::export_text_with`]
+        then use the `transform` attribute of SVG 1.1 instead of a CSS transform. Embedded fonts
+        still need `@font-face`, for renderers without it, either install the fonts or use
+        [`crate::WordCloud::export_rendered_with`]. Defaults to `false`.
+    */
+    pub fn compatible(mut self, compatible: bool) -> Self {
+        self.compatible = compatible;
+        self
+    }
+
+    pub(crate) fn has_word_attributes(&self) -> bool {
+        self.word_ids.is_some() || self.word_class.is_some() || self.word_data
+    }
+
+    /**
+        Sets the configured `id`, `class` and `data-*` attributes of the word at position `index`.
+        `style_class` is the class of its [`crate::WordStyle`], which is kept.
+    */
+    pub(crate) fn apply_word_attributes<N: Node>(
+        &self,
+        node: &mut N,
+        index: usize,
+        info: &PlacedWordInfo,
+        style_class: Option<&str>,
+    ) {
+        if let Some(prefix) = &self.word_ids {
+            node.assign("id", escape_xml(&format!("{}{}", prefix, index)));
+        }
+        if let Some(class) = &self.word_class {
+            let class = match style_class {
+                None => class.clone(),
+                Some(style_class) => format!("{} {}", style_class, class),
+            };
+            node.assign("class", escape_xml(&class));
+        }
+        if self.word_data {
+            node.assign("data-word", escape_xml(info.text));
+            node.assign("data-count", info.count);
+            node.assign("data-rank", info.rank);
+            node.assign("data-font", escape_xml(info.font));
+            node.assign("data-rotation", self.number(info.rotation));
+        }
+    }
+
+    /**
+        Formats a number with the configured precision, without trailing zeros
+    */
+    pub(crate) fn number(&self, value: f32) -> String {
+        format_number(value, self.precision)
+    }
+}
+
+/**
+    Options used by [`crate::WordCloud::export_rendered_with`], which draws the words as paths.
+
+    ```no_run
+    use wordcloud::{ExportOptions, RenderedOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = RenderedOptions::new()
+        .merge_paths(false)
+        .word_labels(true)
+        .export_options(ExportOptions::new().margin(20.));
+
+    let svg = wc.export_rendered_with(&options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct RenderedOptions {
+    pub(crate) merge_paths: bool,
+    pub(crate) word_labels: bool,
+    pub(crate) word_list: bool,
+    pub(crate) export_options: ExportOptions,
+}
+
+impl Default for RenderedOptions {
+    fn default() -> Self {
+        RenderedOptions {
+            merge_paths: true,
+            word_labels: false,
+            word_list: false,
+            export_options: ExportOptions::default(),
+        }
+    }
+}
+
+impl RenderedOptions {
+    /**
+        Construct the default [`RenderedOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Whether all words with the same [`crate::WordStyle`] are drawn by a single path, which
+        makes the SVG a lot smaller. The path data is collected before it is written, so the
+        export isn't streamed anymore. Ignored if any of the per word attributes
+        ([`ExportOptions::word_ids`], [`ExportOptions::word_class`] or
+        [`ExportOptions::word_data`]) are set, which need one path per word. Defaults to `true`.
+    */
+    pub fn merge_paths(mut self, merge_paths: bool) -> Self {
+        self.merge_paths = merge_paths;
+        self
+    }
+
+    /**
+        Whether every path gets an `aria-label` with the words it draws, as the paths have no
+        readable text. Defaults to `false`.
+    */
+    pub fn word_labels(mut self, word_labels: bool) -> Self {
+        self.word_labels = word_labels;
+        self
+    }
+
+    /**
+        Whether an invisible text with all words in rank order is added, which screen readers
+        read as part of the label of the document. Defaults to `false`.
+    */
+    pub fn word_list(mut self, word_list: bool) -> Self {
+        self.word_list = word_list;
+        self
+    }
+
+    /**
+        [`ExportOptions`] used for the SVG
+    */
+    pub fn export_options(mut self, export_options: ExportOptions) -> Self {
+        self.export_options = export_options;
+        self
+    }
+}
+
+/**
+    Escapes the characters, which are not allowed in XML text and attribute values
+*/
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn format_number(value: f32, precision: usize) -> String {
+    let mut formatted = format!("{:.*}", precision, value);
+    if formatted.contains('.') {
+        let trimmed = formatted.trim_end_matches('0').trim_end_matches('.').len();
+        formatted.truncate(trimmed);
+    }
+    if formatted == "-0" {
+        formatted = String::from("0");
+    }
+    formatted
+}
+
+#[test]
+fn test_format_number() {
+    assert_eq!(format_number(582.1417, 2), "582.14");
+    assert_eq!(format_number(200., 2), "200");
+    assert_eq!(format_number(0.101, 2), "0.1");
+    assert_eq!(format_number(-0.001, 2), "0");
+    assert_eq!(format_number(12.4, 0), "12");
+}
+
+#[test]
+fn test_word_attributes() {
+    let info = PlacedWordInfo {
+        text: "R&D",
+        count: 12,
+        rank: 3,
+        weight: 0.5,
+        font: "Open Sans",
+        font_size: 20.,
+        rotation: 90.,
+        x: 0.,
+        y: 0.,
+        width: 10.,
+        height: 10.,
+        region: None,
+        color: None,
+    };
+    let options = ExportOptions::new()
+        .word_ids("word-")
+        .word_class("cloud-word")
+        .word_data(true);
+
+    let mut text = svg::node::element::Text::new();
+    options.apply_word_attributes(&mut text, 3, &info, Some("big"));
+    let attributes = text.get_attributes();
+
+    assert_eq!(attributes["id"].to_string(), "word-3");
+    assert_eq!(attributes["class"].to_string(), "big cloud-word");
+    assert_eq!(attributes["data-word"].to_string(), "R&amp;D");
+    assert_eq!(attributes["data-rank"].to_string(), "3");
+    assert_eq!(attributes["data-rotation"].to_string(), "90");
+}
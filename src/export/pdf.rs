@@ -0,0 +1,833 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Error, Write};
+
+use crate::cloud::word::Word;
+use crate::color::Color;
+use crate::common::font::Font;
+use crate::common::svg_command::SVGPathCommand;
+use crate::export::subset::{face_data, subset_font};
+use crate::export::{format_number, Background, ExportOptions};
+use crate::types::point::Point;
+use crate::WordCloud;
+
+/// Unit of PDF page sizes, points per inch
+const POINTS_PER_INCH: f32 = 72.;
+/// Maximum number of entries in a single `bfchar` block of a CMap
+const CMAP_BLOCK_SIZE: usize = 100;
+
+/**
+    How the words are written to the PDF, like the SVG exporters
+    [`crate::WordCloud::export_rendered`] and [`crate::WordCloud::export_text`].
+*/
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PdfContent {
+    /// The outlines of the glyphs are drawn as vector paths. Needs no fonts to display, but the
+    /// text can't be selected or searched.
+    #[default]
+    Paths,
+    /// The words are written as text with the used fonts embedded into the document.
+    Text,
+}
+
+/**
+    Physical size of a PDF page in points (1/72 inch).
+*/
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PageSize {
+    pub const A3: PageSize = PageSize {
+        width: 841.89,
+        height: 1190.55,
+    };
+    pub const A4: PageSize = PageSize {
+        width: 595.28,
+        height: 841.89,
+    };
+    pub const A5: PageSize = PageSize {
+        width: 419.53,
+        height: 595.28,
+    };
+    pub const LETTER: PageSize = PageSize {
+        width: 612.,
+        height: 792.,
+    };
+    pub const LEGAL: PageSize = PageSize {
+        width: 612.,
+        height: 1008.,
+    };
+
+    /**
+        Page size from its width and height in millimeters
+    */
+    pub fn from_millimeters(width: f32, height: f32) -> Self {
+        PageSize::from_inches(width / 25.4, height / 25.4)
+    }
+
+    /**
+        Page size from its width and height in inches
+    */
+    pub fn from_inches(width: f32, height: f32) -> Self {
+        PageSize {
+            width: width * POINTS_PER_INCH,
+            height: height * POINTS_PER_INCH,
+        }
+    }
+
+    /**
+        The same page, turned so that it is at least as wide as it is high
+    */
+    pub fn landscape(self) -> Self {
+        PageSize {
+            width: self.width.max(self.height),
+            height: self.width.min(self.height),
+        }
+    }
+}
+
+/**
+    Options used by [`crate::WordCloud::export_pdf_with`].
+
+    ```no_run
+    use wordcloud::{ExportOptions, PageSize, PdfContent, PdfOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = PdfOptions::new()
+        .page_size(PageSize::A4.landscape())
+        .export_options(ExportOptions::new().margin(20.));
+
+    let pdf = wc.export_pdf_with(PdfContent::Text, &options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug, Default)]
+pub struct PdfOptions {
+    page_size: Option<PageSize>,
+    dpi: Option<f32>,
+    export_options: ExportOptions,
+}
+
+impl PdfOptions {
+    /**
+        Construct the default [`PdfOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Size of the page the WordCloud is placed on. Without it, the page has exactly the size
+        of the WordCloud.
+    */
+    pub fn page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /**
+        Resolution the [`crate::Dimensions`] are measured in, which decides the physical size of
+        the WordCloud. Defaults to `72`, so that one pixel is one point, or to fitting the page,
+        if a [`PageSize`] is set.
+    */
+    pub fn dpi(mut self, dpi: f32) -> Self {
+        self.dpi = Some(dpi);
+        self
+    }
+
+    /**
+        [`ExportOptions`] used for the PDF. The background, margin, precision and
+        [`ExportOptions::subset_fonts`] are used.
+    */
+    pub fn export_options(mut self, export_options: ExportOptions) -> Self {
+        self.export_options = export_options;
+        self
+    }
+}
+
+/**
+    Writes the objects of a PDF file and keeps track of their offsets for the
+    cross-reference table.
+*/
+struct PdfWriter<W: Write> {
+    target: W,
+    position: usize,
+    offsets: Vec<usize>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    fn new(target: W) -> Result<Self, Error> {
+        let mut writer = PdfWriter {
+            target,
+            position: 0,
+            offsets: Vec::new(),
+        };
+        // the binary comment marks the file as binary for transfer programs
+        writer.write(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n")?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.target.write_all(data)?;
+        self.position += data.len();
+        Ok(())
+    }
+
+    /**
+        Reserves the number of an object, which can be referenced before it is written
+    */
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn object(&mut self, id: usize, dictionary: &str) -> Result<(), Error> {
+        self.offsets[id - 1] = self.position;
+        self.write(format!("{} 0 obj\n{}\nendobj\n", id, dictionary).as_bytes())
+    }
+
+    fn stream(&mut self, id: usize, entries: &str, data: &[u8]) -> Result<(), Error> {
+        self.offsets[id - 1] = self.position;
+        self.write(
+            format!(
+                "{} 0 obj\n<< /Length {}{} >>\nstream\n",
+                id,
+                data.len(),
+                entries
+            )
+            .as_bytes(),
+        )?;
+        self.write(data)?;
+        self.write(b"\nendstream\nendobj\n")
+    }
+
+    /**
+        Writes the cross-reference table and the trailer and returns the target
+    */
+    fn finish(mut self, root: usize) -> Result<W, Error> {
+        let xref = self.position;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root,
+            xref
+        );
+        self.write(table.as_bytes())?;
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+/**
+    Glyphs of a font written in [`PdfContent::Text`] mode, with the characters they represent
+*/
+struct PdfFont<'f> {
+    font: &'f Font<'f>,
+    resource: String,
+    glyphs: BTreeMap<u16, char>,
+}
+
+impl<'f> PdfFont<'f> {
+    fn table(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        self.font.reference().table(u32::from_be_bytes(*tag))
+    }
+
+    /**
+        Bounding box of all glyphs from the `head` table, in glyph space units
+    */
+    fn bounding_box(&self, scale: f32) -> [f32; 4] {
+        match self.table(b"head") {
+            Some(head) if head.len() >= 44 => {
+                let value = |at: usize| i16::from_be_bytes([head[at], head[at + 1]]) as f32;
+                [value(36), value(38), value(40), value(42)].map(|v| v * scale)
+            }
+            _ => [0., -200., 1000., 1000.],
+        }
+    }
+
+    fn to_unicode(&self) -> String {
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+            /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+            /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+            1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+
+        let glyphs = self.glyphs.iter().collect::<Vec<_>>();
+        for block in glyphs.chunks(CMAP_BLOCK_SIZE) {
+            let _ = writeln!(cmap, "{} beginbfchar", block.len());
+            for (glyph, char) in block {
+                let mut units = [0; 2];
+                let _ = write!(cmap, "<{:04X}> <", glyph);
+                for unit in char.encode_utf16(&mut units) {
+                    let _ = write!(cmap, "{:04X}", unit);
+                }
+                cmap.push_str(">\n");
+            }
+            cmap.push_str("endbfchar\n");
+        }
+
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend");
+        cmap
+    }
+
+    /**
+        Writes the font as a composite font with the glyph ids as character codes and returns
+        the number of the font object
+    */
+    fn write_to<W: Write>(&self, writer: &mut PdfWriter<W>, subset: bool) -> Result<usize, Error> {
+        let (type0, cid_font, descriptor, file, to_unicode) = (
+            writer.reserve(),
+            writer.reserve(),
+            writer.reserve(),
+            writer.reserve(),
+            writer.reserve(),
+        );
+
+        let reference = self.font.reference();
+        let metrics = reference.metrics(&[]);
+        let scale = 1000. / metrics.units_per_em.max(1) as f32;
+        let glyph_metrics = reference.glyph_metrics(&[]);
+        let name = pdf_name(self.font.name());
+        let is_cff = self.table(b"CFF ").is_some() || self.table(b"CFF2").is_some();
+
+        let mut widths = String::new();
+        for glyph in self.glyphs.keys() {
+            let _ = write!(
+                widths,
+                "{} [{}] ",
+                glyph,
+                format_number(glyph_metrics.advance_width(*glyph) * scale, 0)
+            );
+        }
+
+        writer.object(
+            type0,
+            &format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+                /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                name, cid_font, to_unicode
+            ),
+        )?;
+        writer.object(
+            cid_font,
+            &format!(
+                "<< /Type /Font /Subtype /{} /BaseFont /{} \
+                /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                /FontDescriptor {} 0 R /W [{}]{} >>",
+                if is_cff {
+                    "CIDFontType0"
+                } else {
+                    "CIDFontType2"
+                },
+                name,
+                descriptor,
+                widths.trim_end(),
+                if is_cff {
+                    ""
+                } else {
+                    " /CIDToGIDMap /Identity"
+                }
+            ),
+        )?;
+
+        let [x_min, y_min, x_max, y_max] = self.bounding_box(scale);
+        writer.object(
+            descriptor,
+            &format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [{} {} {} {}] \
+                /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /{} {} 0 R >>",
+                name,
+                format_number(x_min, 0),
+                format_number(y_min, 0),
+                format_number(x_max, 0),
+                format_number(y_max, 0),
+                format_number(metrics.ascent * scale, 0),
+                format_number(-metrics.descent.abs() * scale, 0),
+                format_number(metrics.cap_height * scale, 0),
+                if is_cff { "FontFile3" } else { "FontFile2" },
+                file
+            ),
+        )?;
+
+        let subset = match subset {
+            false => None,
+            true => subset_font(
+                reference.data,
+                reference.offset as usize,
+                &self.glyphs.keys().copied().collect(),
+            )
+            .map(|s| s.write()),
+        };
+        let data = match subset {
+            None => face_data(reference.data, reference.offset as usize),
+            Some(subset) => Cow::Owned(subset),
+        };
+        if is_cff {
+            writer.stream(file, " /Subtype /OpenType", &data)?;
+        } else {
+            writer.stream(file, &format!(" /Length1 {}", data.len()), &data)?;
+        }
+        writer.stream(to_unicode, "", self.to_unicode().as_bytes())?;
+
+        Ok(type0)
+    }
+}
+
+/**
+    Escapes all characters, which are not allowed in a PDF name
+*/
+fn pdf_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'!'..=b'~' if !b"#()<>[]{}/%".contains(&byte) => escaped.push(byte as char),
+            _ => {
+                let _ = write!(escaped, "#{:02X}", byte);
+            }
+        }
+    }
+    escaped
+}
+
+fn pdf_color(color: &Color) -> String {
+    format!(
+        "{} {} {}",
+        format_number(color.r as f32 / 255., 3),
+        format_number(color.g as f32 / 255., 3),
+        format_number(color.b as f32 / 255., 3)
+    )
+}
+
+/**
+    Resources referenced by the content stream of the page
+*/
+#[derive(Default)]
+struct Resources {
+    /// Graphics states by their fill and stroke opacity
+    opacities: BTreeMap<(String, String), String>,
+    /// Shading dictionaries by their name
+    shadings: Vec<(String, String)>,
+}
+
+impl Resources {
+    fn opacity(&mut self, fill: f32, stroke: f32) -> String {
+        let key = (
+            format_number(fill.clamp(0., 1.), 3),
+            format_number(stroke.clamp(0., 1.), 3),
+        );
+        let next = format!("GS{}", self.opacities.len());
+        self.opacities.entry(key).or_insert(next).clone()
+    }
+
+    fn shading(&mut self, dictionary: String) -> String {
+        let name = format!("Sh{}", self.shadings.len());
+        self.shadings.push((name.clone(), dictionary));
+        name
+    }
+}
+
+impl<'a> WordCloud<'a> {
+    fn pdf_background(
+        &self,
+        options: &ExportOptions,
+        resources: &mut Resources,
+        content: &mut String,
+    ) {
+        let margin = options.margin;
+        let area = format!(
+            "{} {} {} {}",
+            options.number(-margin),
+            options.number(-margin),
+            options.number(self.dimensions.width() as f32 + 2. * margin),
+            options.number(self.dimensions.height() as f32 + 2. * margin)
+        );
+        let function = |from: &Color, to: &Color| {
+            format!(
+                "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+                pdf_color(from),
+                pdf_color(to)
+            )
+        };
+
+        let shading = match &options.background {
+            Background::None => return,
+            Background::Color(c) => {
+                let _ = write!(
+                    content,
+                    "q\n/{} gs\n",
+                    resources.opacity(c.a as f32 / 255., 1.)
+                );
+                let _ = write!(content, "{} rg\n{} re\nf\nQ\n", pdf_color(c), area);
+                return;
+            }
+            Background::LinearGradient { from, to, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                resources.shading(format!(
+                    "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] \
+                    /Function {} /Extend [true true] >>",
+                    format_number(0.5 - cos / 2., 4),
+                    format_number(0.5 - sin / 2., 4),
+                    format_number(0.5 + cos / 2., 4),
+                    format_number(0.5 + sin / 2., 4),
+                    function(from, to)
+                ))
+            }
+            Background::RadialGradient { inner, outer } => resources.shading(format!(
+                "<< /ShadingType 3 /ColorSpace /DeviceRGB /Coords [0.5 0.5 0 0.5 0.5 0.5] \
+                /Function {} /Extend [true true] >>",
+                function(inner, outer)
+            )),
+        };
+
+        // like in SVG, the gradient is defined relative to the bounding box of the background
+        let _ = write!(
+            content,
+            "q\n{} re\nW n\n{} 0 0 {} {} {} cm\n/{} sh\nQ\n",
+            area,
+            options.number(self.dimensions.width() as f32 + 2. * margin),
+            options.number(self.dimensions.height() as f32 + 2. * margin),
+            options.number(-margin),
+            options.number(-margin),
+            shading
+        );
+    }
+
+    fn pdf_path(word: &Word, options: &ExportOptions, content: &mut String) {
+        let point = |p: Point<f32>| format!("{} {}", options.number(p.x), options.number(p.y));
+
+        for letter in &word.glyphs {
+            let off = letter.rotation.rotate_point(letter.offset) + word.offset;
+            for command in &letter.state {
+                let _ = match command {
+                    SVGPathCommand::Move(m) => writeln!(content, "{} m", point(m.position + off)),
+                    SVGPathCommand::Line(l) => writeln!(content, "{} l", point(l.end + off)),
+                    SVGPathCommand::QuadCurve(q) => writeln!(
+                        content,
+                        "{} {} {} c",
+                        point(q.s + (q.c1 - q.s) * (2. / 3.) + off),
+                        point(q.e + (q.c1 - q.e) * (2. / 3.) + off),
+                        point(q.e + off)
+                    ),
+                    SVGPathCommand::Curve(c) => writeln!(
+                        content,
+                        "{} {} {} c",
+                        point(c.c2 + off),
+                        point(c.c1 + off),
+                        point(c.e + off)
+                    ),
+                    SVGPathCommand::End(_) => writeln!(content, "h"),
+                };
+            }
+        }
+    }
+
+    fn pdf_text<'f>(
+        word: &Word<'f>,
+        fonts: &mut Vec<PdfFont<'f>>,
+        options: &ExportOptions,
+        content: &mut String,
+    ) {
+        let (sin, cos) = word.rotation.inner().to_radians().sin_cos();
+        let size = word.scale;
+
+        content.push_str("BT\n");
+        for run in &word.runs {
+            let index = match fonts.iter().position(|f| f.font == run.font) {
+                Some(index) => index,
+                None => {
+                    fonts.push(PdfFont {
+                        font: run.font,
+                        resource: format!("F{}", fonts.len()),
+                        glyphs: BTreeMap::new(),
+                    });
+                    fonts.len() - 1
+                }
+            };
+            let font = &mut fonts[index];
+            let _ = writeln!(content, "/{} 1 Tf", font.resource);
+
+            for letter in &word.glyphs[run.glyphs.clone()] {
+                font.glyphs.entry(letter.glyph_id).or_insert(letter.char);
+                let origin = letter.rotation.rotate_point(letter.offset) + word.offset;
+                // the page is flipped vertically, so the glyphs have to be flipped back
+                let _ = writeln!(
+                    content,
+                    "{} {} {} {} {} {} Tm <{:04X}> Tj",
+                    format_number(size * cos, 4),
+                    format_number(size * sin, 4),
+                    format_number(size * sin, 4),
+                    format_number(-size * cos, 4),
+                    options.number(origin.x),
+                    options.number(origin.y),
+                    letter.glyph_id
+                );
+            }
+        }
+        content.push_str("ET\n");
+    }
+
+    /**
+        Export the resulting WordCloud as a single page PDF. The words are either drawn as
+        vector paths or written as text with embedded fonts, see [`PdfContent`].
+
+        One pixel of the [`crate::Dimensions`] is one point (1/72 inch) on the page, use
+        [`Self::export_pdf_with`] to choose the DPI or a page size.
+    */
+    pub fn export_pdf(&self, content: PdfContent) -> Result<Vec<u8>, Error> {
+        self.export_pdf_with(content, &PdfOptions::default())
+    }
+
+    /**
+        Like [`Self::export_pdf`], but customized with [`PdfOptions`]. With a page size but
+        without a DPI, the WordCloud is scaled to fit the page. It is always centered on the page.
+
+        With [`ExportOptions::subset_fonts`], the embedded fonts only contain the used glyphs.
+        CSS classes of the [`crate::WordStyle`] and the transparency of gradient colors are
+        ignored.
+    */
+    pub fn export_pdf_with(
+        &self,
+        content: PdfContent,
+        pdf_options: &PdfOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let options = &pdf_options.export_options;
+        let margin = options.margin;
+        let width = self.dimensions.width() as f32 + 2. * margin;
+        let height = self.dimensions.height() as f32 + 2. * margin;
+
+        let (page, points_per_pixel) = match (pdf_options.page_size, pdf_options.dpi) {
+            (None, dpi) => {
+                let scale = POINTS_PER_INCH / dpi.unwrap_or(POINTS_PER_INCH);
+                (
+                    PageSize {
+                        width: width * scale,
+                        height: height * scale,
+                    },
+                    scale,
+                )
+            }
+            (Some(page), Some(dpi)) => (page, POINTS_PER_INCH / dpi),
+            (Some(page), None) => (page, (page.width / width).min(page.height / height)),
+        };
+
+        let mut stream = String::new();
+        let _ = write!(
+            stream,
+            "q\n{} 0 0 {} {} {} cm\n",
+            format_number(points_per_pixel, 6),
+            format_number(-points_per_pixel, 6),
+            format_number(
+                (page.width - width * points_per_pixel) / 2. + margin * points_per_pixel,
+                3
+            ),
+            format_number(
+                page.height
+                    - (page.height - height * points_per_pixel) / 2.
+                    - margin * points_per_pixel,
+                3
+            )
+        );
+
+        let mut resources = Resources::default();
+        self.pdf_background(options, &mut resources, &mut stream);
+
+        let ct = self.ct.read();
+        let words = WordCloud::sorted_words(&ct);
+        let rank_count = WordCloud::rank_count(&words);
+        let mut fonts: Vec<PdfFont> = Vec::new();
+
+        for word in words {
+            let style = self.style_for_word(word, rank_count);
+            let fill = style.fill.unwrap_or(Color::rgb(0, 0, 0));
+            let opacity = style.opacity.unwrap_or(1.);
+            let fill_opacity = fill.a as f32 / 255. * opacity;
+            let stroke_opacity = style.stroke.map_or(1., |s| s.a as f32 / 255.) * opacity;
+
+            stream.push_str("q\n");
+            if fill_opacity < 1. || stroke_opacity < 1. {
+                let _ = writeln!(
+                    stream,
+                    "/{} gs",
+                    resources.opacity(fill_opacity, stroke_opacity)
+                );
+            }
+            let _ = writeln!(stream, "{} rg", pdf_color(&fill));
+            if let Some(stroke) = style.stroke {
+                let _ = writeln!(
+                    stream,
+                    "{} RG\n{} w",
+                    pdf_color(&stroke),
+                    options.number(style.stroke_width.unwrap_or(1.))
+                );
+            }
+
+            match content {
+                PdfContent::Paths => {
+                    WordCloud::pdf_path(word, options, &mut stream);
+                    stream.push_str(if style.stroke.is_some() { "B\n" } else { "f\n" });
+                }
+                PdfContent::Text => {
+                    if style.stroke.is_some() {
+                        stream.push_str("2 Tr\n");
+                    }
+                    WordCloud::pdf_text(word, &mut fonts, options, &mut stream);
+                }
+            }
+            stream.push_str("Q\n");
+        }
+        stream.push('Q');
+
+        let mut writer = PdfWriter::new(Vec::new())?;
+        let (catalog, pages, page_object, contents) = (
+            writer.reserve(),
+            writer.reserve(),
+            writer.reserve(),
+            writer.reserve(),
+        );
+
+        let mut resource_dictionary = String::new();
+        if !fonts.is_empty() {
+            resource_dictionary.push_str(" /Font <<");
+            for font in &fonts {
+                let id = font.write_to(&mut writer, options.subset_fonts)?;
+                let _ = write!(resource_dictionary, " /{} {} 0 R", font.resource, id);
+            }
+            resource_dictionary.push_str(" >>");
+        }
+        if !resources.opacities.is_empty() {
+            resource_dictionary.push_str(" /ExtGState <<");
+            for ((fill, stroke), name) in &resources.opacities {
+                let _ = write!(
+                    resource_dictionary,
+                    " /{} << /ca {} /CA {} >>",
+                    name, fill, stroke
+                );
+            }
+            resource_dictionary.push_str(" >>");
+        }
+        if !resources.shadings.is_empty() {
+            resource_dictionary.push_str(" /Shading <<");
+            for (name, shading) in &resources.shadings {
+                let _ = write!(resource_dictionary, " /{} {}", name, shading);
+            }
+            resource_dictionary.push_str(" >>");
+        }
+
+        writer.object(
+            catalog,
+            &format!("<< /Type /Catalog /Pages {} 0 R >>", pages),
+        )?;
+        writer.object(
+            pages,
+            &format!("<< /Type /Pages /Kids [{} 0 R] /Count 1 >>", page_object),
+        )?;
+        writer.object(
+            page_object,
+            &format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources <<{} >> /Contents {} 0 R >>",
+                pages,
+                format_number(page.width, 3),
+                format_number(page.height, 3),
+                resource_dictionary,
+                contents
+            ),
+        )?;
+        writer.stream(contents, "", stream.as_bytes())?;
+
+        writer.finish(catalog)
+    }
+
+    /**
+        Writes the result of [`Self::export_pdf`] to a file.
+    */
+    pub fn export_pdf_to_file(&self, filename: &str, content: PdfContent) -> Result<(), Error> {
+        let pdf = self.export_pdf(content)?;
+        std::fs::write(filename, pdf)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_pdf_writer() {
+    let mut writer = PdfWriter::new(Vec::new()).unwrap();
+    let (catalog, pages) = (writer.reserve(), writer.reserve());
+    writer
+        .object(pages, "<< /Type /Pages /Kids [] /Count 0 >>")
+        .unwrap();
+    writer
+        .object(
+            catalog,
+            &format!("<< /Type /Catalog /Pages {} 0 R >>", pages),
+        )
+        .unwrap();
+    let pdf = writer.finish(catalog).unwrap();
+    let text = String::from_utf8_lossy(&pdf).to_string();
+
+    let offset = |id: usize| {
+        let entry = text
+            .split("xref\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .nth(1 + id)
+            .unwrap();
+        entry[0..10].parse::<usize>().unwrap()
+    };
+    assert!(pdf[offset(1)..].starts_with(b"1 0 obj\n<< /Type /Catalog"));
+    assert!(pdf[offset(2)..].starts_with(b"2 0 obj\n<< /Type /Pages"));
+    assert_eq!(pdf_name("Noto Sans/Bold"), "Noto#20Sans#2FBold");
+}
+
+#[test]
+fn test_pdf_content() {
+    use crate::cloud::word_cloud::{test_font_set, test_words};
+    use crate::cloud::WordStyle;
+    use crate::{Dimensions, WordCloudBuilder};
+
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(600, 600))
+        .font(&font_set)
+        .style(|info| WordStyle {
+            stroke: (info.rank == 0).then(|| Color::rgb(0, 0, 255)),
+            opacity: Some(0.5),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    wc.write_content(test_words(&[("outlined", 16), ("filled", 8)]), 10)
+        .unwrap();
+
+    let paths = String::from_utf8_lossy(&wc.export_pdf(PdfContent::Paths).unwrap()).to_string();
+    assert!(!paths.contains("/Type /Font"));
+    assert!(paths.contains("/ca 0.5 /CA 0.5"));
+    assert!(paths.contains("0 0 1 RG\n"));
+    assert!(paths.contains("B\n"));
+    assert!(paths.contains("f\n"));
+    assert!(!paths.contains("Tj"));
+
+    let text = String::from_utf8_lossy(&wc.export_pdf(PdfContent::Text).unwrap()).to_string();
+    assert_eq!(text.matches("/Subtype /Type0").count(), 1);
+    assert!(text.contains("/Encoding /Identity-H"));
+    assert!(text.contains("/Type /FontDescriptor"));
+    assert!(text.contains("/ToUnicode"));
+    assert!(text.contains("/F0 1 Tf"));
+    assert_eq!(text.matches("BT\n").count(), 2);
+    // every glyph is positioned and shown on its own
+    assert!(text.matches(" Tj\n").count() >= "outlined".len());
+    assert_eq!(text.matches("2 Tr\n").count(), 1);
+}
@@ -0,0 +1,435 @@
+use std::io::{BufWriter, Error, Write};
+
+use svg::node::element::{Group, Path};
+
+use crate::color::Color;
+use crate::common::svg_command::PathEncoder;
+use crate::export::svg_writer::SvgWriter;
+use crate::export::ExportOptions;
+use crate::types::point::Point;
+use crate::WordCloud;
+
+/**
+    Options used by [`crate::WordCloud::export_plotter_with`].
+
+    ```no_run
+    use wordcloud::{ExportOptions, PlotterOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = PlotterOptions::new()
+        .tolerance(0.05)
+        .hatch(1.5, 45.)
+        .export_options(ExportOptions::new().margin(20.));
+
+    let svg = wc.export_plotter_with(&options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct PlotterOptions {
+    tolerance: f32,
+    stroke_width: f32,
+    hatch: Option<(f32, f32)>,
+    optimize_travel: bool,
+    export_options: ExportOptions,
+}
+
+impl Default for PlotterOptions {
+    fn default() -> Self {
+        PlotterOptions {
+            tolerance: 0.1,
+            stroke_width: 0.5,
+            hatch: None,
+            optimize_travel: true,
+            export_options: ExportOptions::default(),
+        }
+    }
+}
+
+impl PlotterOptions {
+    /**
+        Construct the default [`PlotterOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Maximum distance in pixels between a curve of a glyph and the lines it is flattened to,
+        defaults to `0.1`
+    */
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /**
+        Width of the strokes in pixels, which should match the width of the pen. Only affects the
+        preview, the plotter draws with whatever pen is inserted. Defaults to `0.5`.
+    */
+    pub fn stroke_width(mut self, stroke_width: f32) -> Self {
+        self.stroke_width = stroke_width;
+        self
+    }
+
+    /**
+        Fills the glyphs with parallel lines `spacing` pixels apart, rotated clockwise by `angle`
+        degrees. Without it, only the outlines are drawn.
+    */
+    pub fn hatch(mut self, spacing: f32, angle: f32) -> Self {
+        self.hatch = Some((spacing, angle));
+        self
+    }
+
+    /**
+        Whether the strokes are reordered to keep the distance the lifted pen travels short.
+        Otherwise the words are drawn in rank order. Defaults to `true`.
+    */
+    pub fn optimize_travel(mut self, optimize_travel: bool) -> Self {
+        self.optimize_travel = optimize_travel;
+        self
+    }
+
+    /**
+        [`ExportOptions`] used for the SVG. Backgrounds and styles other than the fill color are
+        ignored, as they can't be plotted.
+    */
+    pub fn export_options(mut self, export_options: ExportOptions) -> Self {
+        self.export_options = export_options;
+        self
+    }
+}
+
+/**
+    A line drawn without lifting the pen. Closed strokes return to their first point.
+*/
+#[derive(Clone, Debug)]
+struct Stroke {
+    points: Vec<Point<f32>>,
+    closed: bool,
+}
+
+impl Stroke {
+    /**
+        Distance from `position` to the nearest point this stroke can start at, and that point
+    */
+    fn nearest_start(&self, position: Point<f32>) -> (f32, usize) {
+        let candidates: Box<dyn Iterator<Item = usize>> = if self.closed {
+            Box::new(0..self.points.len())
+        } else {
+            Box::new([0, self.points.len() - 1].into_iter())
+        };
+
+        candidates
+            .map(|i| (self.points[i].distance(&position), i))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((0., 0))
+    }
+
+    /**
+        Changes the direction or the first point, so that the stroke starts at `start`
+    */
+    fn start_at(&mut self, start: usize) {
+        if self.closed {
+            self.points.rotate_left(start);
+        } else if start != 0 {
+            self.points.reverse();
+        }
+    }
+
+    /**
+        The position of the pen after the stroke
+    */
+    fn end(&self) -> Point<f32> {
+        if self.closed {
+            self.points[0]
+        } else {
+            self.points[self.points.len() - 1]
+        }
+    }
+
+    fn encode(&self, encoder: &mut PathEncoder) {
+        encoder.move_to(self.points[0]);
+        for p in &self.points[1..] {
+            encoder.line_to(*p);
+        }
+        if self.closed {
+            encoder.close();
+        }
+    }
+}
+
+/**
+    Greedily orders the strokes, so that every stroke starts as near as possible to the end of
+    the previous one. Returns the position of the pen afterwards.
+*/
+fn order_for_travel(strokes: &mut Vec<Stroke>, mut position: Point<f32>) -> Point<f32> {
+    let mut remaining = std::mem::take(strokes);
+    while let Some((index, start)) = remaining
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (i, s.nearest_start(position)))
+        .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+        .map(|(i, (_, start))| (i, start))
+    {
+        let mut stroke = remaining.swap_remove(index);
+        stroke.start_at(start);
+        position = stroke.end();
+        strokes.push(stroke);
+    }
+    position
+}
+
+/**
+    Lines `spacing` apart at `angle` degrees, which fill the polygons with the non-zero winding
+    rule. The lines lie on a global grid, so that the hatching of neighbouring words lines up, and
+    alternate their direction.
+*/
+fn hatch(polygons: &[Vec<Point<f32>>], spacing: f32, angle: f32) -> Vec<Stroke> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let rotate = |p: &Point<f32>, sin: f32| Point {
+        x: p.x * cos - p.y * sin,
+        y: p.x * sin + p.y * cos,
+    };
+
+    // rotate the polygons, so that the hatch lines are horizontal
+    let edges = polygons
+        .iter()
+        .flat_map(|polygon| {
+            let rotated: Vec<Point<f32>> = polygon.iter().map(|p| rotate(p, -sin)).collect();
+            (0..rotated.len())
+                .map(|i| (rotated[i], rotated[(i + 1) % rotated.len()]))
+                .collect::<Vec<_>>()
+        })
+        .filter(|(a, b)| a.y != b.y)
+        .collect::<Vec<(Point<f32>, Point<f32>)>>();
+
+    if edges.is_empty() {
+        return Vec::new();
+    }
+    let spacing = spacing.max(0.01);
+    let min_y = edges
+        .iter()
+        .map(|(a, b)| a.y.min(b.y))
+        .fold(f32::MAX, f32::min);
+    let max_y = edges
+        .iter()
+        .map(|(a, b)| a.y.max(b.y))
+        .fold(f32::MIN, f32::max);
+
+    let mut strokes = Vec::new();
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for line in (min_y / spacing).ceil() as i64..=(max_y / spacing).floor() as i64 {
+        let y = line as f32 * spacing;
+
+        crossings.clear();
+        crossings.extend(
+            edges
+                .iter()
+                .filter(|(a, b)| a.y.min(b.y) <= y && y < a.y.max(b.y))
+                .map(|(a, b)| {
+                    let t = (y - a.y) / (b.y - a.y);
+                    (a.x + (b.x - a.x) * t, if a.y < b.y { 1 } else { -1 })
+                }),
+        );
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut spans = Vec::new();
+        let mut winding = 0;
+        let mut span_start = 0.;
+        for (x, direction) in &crossings {
+            if winding == 0 {
+                span_start = *x;
+            }
+            winding += direction;
+            if winding == 0 && *x > span_start {
+                spans.push((span_start, *x));
+            }
+        }
+
+        if line % 2 != 0 {
+            spans.reverse();
+        }
+        strokes.extend(spans.into_iter().map(|(start, end)| {
+            let (start, end) = if line % 2 == 0 {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            Stroke {
+                points: vec![
+                    rotate(&Point { x: start, y }, sin),
+                    rotate(&Point { x: end, y }, sin),
+                ],
+                closed: false,
+            }
+        }));
+    }
+
+    strokes
+}
+
+impl<'a> WordCloud<'a> {
+    /**
+        Export the resulting WordCloud as an SVG for pen plotters. The glyphs are drawn as
+        outlines made of straight lines, optionally filled by hatching. Every color gets its own
+        Inkscape layer, so that it can be plotted with its own pen.
+    */
+    pub fn export_plotter(&self) -> Result<String, Error> {
+        self.export_plotter_with(&PlotterOptions::default())
+    }
+
+    /**
+        Like [`Self::export_plotter`], but customized with [`PlotterOptions`].
+    */
+    pub fn export_plotter_with(&self, options: &PlotterOptions) -> Result<String, Error> {
+        let mut target = Vec::new();
+        self.write_plotter_to(&mut target, options)?;
+
+        Ok(String::from_utf8(target).expect("decoding the written string failed"))
+    }
+
+    /**
+        Writes the result of [`Self::export_plotter_with`] into `target`.
+    */
+    pub fn write_plotter_to<W: Write>(
+        &self,
+        target: W,
+        options: &PlotterOptions,
+    ) -> Result<(), Error> {
+        let export_options = &options.export_options;
+        let ct = self.ct.read();
+        let words = WordCloud::sorted_words(&ct);
+        let rank_count = WordCloud::rank_count(&words);
+
+        // the strokes of every word, in layers of the same color
+        let mut layers: Vec<(Color, Vec<Vec<Stroke>>)> = Vec::new();
+        for word in &words {
+            let color = self
+                .style_for_word(word, rank_count)
+                .fill
+                .unwrap_or(Color::rgb(0, 0, 0));
+
+            let polygons = word.polygons(options.tolerance);
+            let mut strokes: Vec<Stroke> = polygons
+                .iter()
+                .map(|polygon| Stroke {
+                    points: polygon.clone(),
+                    closed: true,
+                })
+                .collect();
+            if let Some((spacing, angle)) = options.hatch {
+                strokes.extend(hatch(&polygons, spacing, angle));
+            }
+            if strokes.is_empty() {
+                continue;
+            }
+
+            match layers.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, layer)) => layer.push(strokes),
+                None => layers.push((color, vec![strokes])),
+            }
+        }
+
+        let mut writer = SvgWriter::new(target, export_options.formatting);
+        writer.start(
+            &self
+                .svg_document(export_options, self.canvas(export_options), false)
+                .set(
+                    "xmlns:inkscape",
+                    "http://www.inkscape.org/namespaces/inkscape",
+                ),
+        )?;
+        WordCloud::write_metadata(&mut writer, export_options)?;
+
+        for (index, (color, mut layer)) in layers.into_iter().enumerate() {
+            if options.optimize_travel {
+                WordCloud::order_words_for_travel(&mut layer);
+            }
+
+            let mut encoder = PathEncoder::new(export_options.precision);
+            for stroke in layer.iter().flatten() {
+                stroke.encode(&mut encoder);
+            }
+
+            writer.start(
+                &Group::new()
+                    .set("inkscape:groupmode", "layer")
+                    .set("inkscape:label", format!("{} {}", index + 1, color)),
+            )?;
+            writer.node(
+                &Path::new()
+                    .set("d", encoder.finish())
+                    .set("fill", "none")
+                    .set("stroke", color.to_string())
+                    .set("stroke-width", export_options.number(options.stroke_width))
+                    .set("stroke-linecap", "round")
+                    .set("stroke-linejoin", "round"),
+            )?;
+            writer.end()?;
+        }
+
+        writer.finish()?;
+        Ok(())
+    }
+
+    /**
+        Orders the words of a layer and the strokes of each word for short pen travel. Words are
+        drawn completely before moving on, which keeps the ordering cheap for large clouds.
+    */
+    fn order_words_for_travel(layer: &mut Vec<Vec<Stroke>>) {
+        let mut position = Point::default();
+        let mut remaining = std::mem::take(layer);
+
+        while let Some(index) = remaining
+            .iter()
+            .enumerate()
+            .map(|(i, strokes)| {
+                let distance = strokes
+                    .iter()
+                    .map(|s| s.nearest_start(position).0)
+                    .fold(f32::MAX, f32::min);
+                (i, distance)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+        {
+            let mut strokes = remaining.swap_remove(index);
+            position = order_for_travel(&mut strokes, position);
+            layer.push(strokes);
+        }
+    }
+
+    /**
+        Writes the result of [`Self::export_plotter`] to a file.
+    */
+    pub fn export_plotter_to_file(&self, filename: &str) -> Result<(), Error> {
+        let file = BufWriter::new(std::fs::File::create(filename)?);
+        self.write_plotter_to(file, &PlotterOptions::default())
+    }
+}
+
+#[test]
+fn test_hatch() {
+    let square = vec![vec![
+        Point { x: 0., y: 0. },
+        Point { x: 10., y: 0. },
+        Point { x: 10., y: 10. },
+        Point { x: 0., y: 10. },
+    ]];
+    let lines = hatch(&square, 4., 0.);
+
+    // lines at y = 0, 4 and 8, alternating direction
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].points[0], Point { x: 10., y: 4. });
+    assert_eq!(lines[1].points[1], Point { x: 0., y: 4. });
+
+    let mut strokes = lines;
+    let end = order_for_travel(&mut strokes, Point { x: 0., y: 10. });
+    assert_eq!(strokes[0].points[0], Point { x: 0., y: 8. });
+    assert_eq!(end, Point { x: 10., y: 0. });
+}
@@ -0,0 +1,580 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageError, ImageOutputFormat, Rgba, RgbaImage};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::color::Color;
+use crate::export::{Background, ExportOptions};
+use crate::types::point::Point;
+use crate::types::rect::Rect;
+use crate::WordCloud;
+
+/// Number of scanlines sampled per pixel row
+const SUB_SCANLINES: usize = 4;
+/// Maximum distance in pixels between a curve and the lines approximating it
+const FLATTEN_TOLERANCE: f32 = 0.2;
+
+#[derive(Clone, Copy)]
+struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    direction: i32,
+}
+
+/**
+    Anti-aliased coverage of a set of polygons inside a rectangle of the canvas
+*/
+struct CoverageMask {
+    x: usize,
+    y: usize,
+    width: usize,
+    coverage: Vec<f32>,
+}
+
+impl CoverageMask {
+    /**
+        Rasterizes the polygons with the non-zero winding rule, clipped to the canvas size
+    */
+    fn rasterize(polygons: &[Vec<Point<f32>>], width: usize, height: usize) -> Option<Self> {
+        let mut edges = polygons
+            .iter()
+            .flat_map(|polygon| {
+                polygon
+                    .iter()
+                    .zip(polygon.iter().cycle().skip(1))
+                    .filter(|(a, b)| a.y != b.y)
+                    .map(|(a, b)| {
+                        if a.y < b.y {
+                            Edge {
+                                x0: a.x,
+                                y0: a.y,
+                                x1: b.x,
+                                y1: b.y,
+                                direction: 1,
+                            }
+                        } else {
+                            Edge {
+                                x0: b.x,
+                                y0: b.y,
+                                x1: a.x,
+                                y1: a.y,
+                                direction: -1,
+                            }
+                        }
+                    })
+            })
+            .collect::<Vec<Edge>>();
+
+        if edges.is_empty() {
+            return None;
+        }
+        edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+
+        let min_x = edges
+            .iter()
+            .map(|e| e.x0.min(e.x1))
+            .fold(f32::MAX, f32::min);
+        let max_x = edges
+            .iter()
+            .map(|e| e.x0.max(e.x1))
+            .fold(f32::MIN, f32::max);
+        let min_y = edges[0].y0;
+        let max_y = edges.iter().map(|e| e.y1).fold(f32::MIN, f32::max);
+
+        let x_start = min_x.floor().max(0.) as usize;
+        let x_end = (max_x.ceil().max(0.) as usize).min(width);
+        let y_start = min_y.floor().max(0.) as usize;
+        let y_end = (max_y.ceil().max(0.) as usize).min(height);
+        if x_start >= x_end || y_start >= y_end {
+            return None;
+        }
+
+        let mask_width = x_end - x_start;
+        let mut coverage = vec![0.; mask_width * (y_end - y_start)];
+        let mut next_edge = 0;
+        let mut active: Vec<Edge> = Vec::new();
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+        for row in y_start..y_end {
+            let row_coverage =
+                &mut coverage[(row - y_start) * mask_width..(row - y_start + 1) * mask_width];
+
+            for sub in 0..SUB_SCANLINES {
+                let sample_y = row as f32 + (sub as f32 + 0.5) / SUB_SCANLINES as f32;
+
+                while next_edge < edges.len() && edges[next_edge].y0 <= sample_y {
+                    active.push(edges[next_edge]);
+                    next_edge += 1;
+                }
+                active.retain(|e| e.y1 > sample_y);
+
+                crossings.clear();
+                crossings.extend(active.iter().filter(|e| e.y0 <= sample_y).map(|e| {
+                    let t = (sample_y - e.y0) / (e.y1 - e.y0);
+                    (e.x0 + (e.x1 - e.x0) * t, e.direction)
+                }));
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    if winding != 0 {
+                        CoverageMask::add_span(
+                            row_coverage,
+                            pair[0].0 - x_start as f32,
+                            pair[1].0 - x_start as f32,
+                            1. / SUB_SCANLINES as f32,
+                        );
+                    }
+                }
+            }
+        }
+
+        Some(CoverageMask {
+            x: x_start,
+            y: y_start,
+            width: mask_width,
+            coverage,
+        })
+    }
+
+    /**
+        Adds the exact horizontal coverage of the span from `start` to `end` to the row
+    */
+    fn add_span(row: &mut [f32], start: f32, end: f32, weight: f32) {
+        let start = start.clamp(0., row.len() as f32);
+        let end = end.clamp(0., row.len() as f32);
+        if end <= start {
+            return;
+        }
+
+        let (first, last) = (start.floor() as usize, end.floor() as usize);
+        if first == last {
+            row[first] += (end - start) * weight;
+            return;
+        }
+
+        row[first] += (first as f32 + 1. - start) * weight;
+        for cell in &mut row[first + 1..last] {
+            *cell += weight;
+        }
+        if last < row.len() {
+            row[last] += (end - last as f32) * weight;
+        }
+    }
+}
+
+/**
+    Canvas with premultiplied RGBA pixels in the range `0.0..=1.0`
+*/
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            pixels: vec![[0.; 4]; width * height],
+        }
+    }
+
+    fn premultiplied(color: &Color, opacity: f32) -> [f32; 4] {
+        let alpha = color.a as f32 / 255. * opacity.clamp(0., 1.);
+        [
+            color.r as f32 / 255. * alpha,
+            color.g as f32 / 255. * alpha,
+            color.b as f32 / 255. * alpha,
+            alpha,
+        ]
+    }
+
+    fn blend(pixel: &mut [f32; 4], source: &[f32; 4], coverage: f32) {
+        let inverse = 1. - source[3] * coverage;
+        for c in 0..4 {
+            pixel[c] = source[c] * coverage + pixel[c] * inverse;
+        }
+    }
+
+    /**
+        Fills the canvas with the background of an image of `full_width`×`full_height` pixels,
+        of which the canvas is the part starting at `origin`
+    */
+    fn fill_background(
+        &mut self,
+        background: &Background,
+        (full_width, full_height): (usize, usize),
+        origin: (usize, usize),
+    ) {
+        let (width, height) = (full_width as f32, full_height as f32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (u, v) = (
+                    ((origin.0 + x) as f32 + 0.5) / width,
+                    ((origin.1 + y) as f32 + 0.5) / height,
+                );
+                let color = match background {
+                    Background::None => return,
+                    Background::Color(c) => *c,
+                    Background::LinearGradient { from, to, angle } => {
+                        let (sin, cos) = angle.to_radians().sin_cos();
+                        from.mix(to, (u - 0.5) * cos + (v - 0.5) * sin + 0.5)
+                    }
+                    Background::RadialGradient { inner, outer } => {
+                        let distance = ((u - 0.5).powi(2) + (v - 0.5).powi(2)).sqrt() * 2.;
+                        inner.mix(outer, distance)
+                    }
+                };
+                self.pixels[y * self.width + x] = Canvas::premultiplied(&color, 1.);
+            }
+        }
+    }
+
+    fn composite(&mut self, mask: &CoverageMask, color: &[f32; 4]) {
+        for (i, coverage) in mask.coverage.iter().enumerate() {
+            if *coverage <= 0. {
+                continue;
+            }
+            let (x, y) = (mask.x + i % mask.width, mask.y + i / mask.width);
+            Canvas::blend(
+                &mut self.pixels[y * self.width + x],
+                color,
+                coverage.min(1.),
+            );
+        }
+    }
+
+    /**
+        Averages blocks of `factor`×`factor` pixels into one
+    */
+    fn downsample(self, factor: usize) -> Canvas {
+        if factor <= 1 {
+            return self;
+        }
+
+        let (width, height) = (self.width / factor, self.height / factor);
+        let mut target = Canvas::new(width, height);
+        let samples = (factor * factor) as f32;
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.; 4];
+                for sy in 0..factor {
+                    for sx in 0..factor {
+                        let pixel = self.pixels[(y * factor + sy) * self.width + x * factor + sx];
+                        for c in 0..4 {
+                            sum[c] += pixel[c];
+                        }
+                    }
+                }
+                target.pixels[y * width + x] = sum.map(|c| c / samples);
+            }
+        }
+        target
+    }
+
+    fn into_image(self) -> RgbaImage {
+        RgbaImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = self.pixels[y as usize * self.width + x as usize];
+            let alpha = pixel[3];
+            let straight = |c: f32| {
+                if alpha > 0. {
+                    ((c / alpha).clamp(0., 1.) * 255.).round() as u8
+                } else {
+                    0
+                }
+            };
+            Rgba([
+                straight(pixel[0]),
+                straight(pixel[1]),
+                straight(pixel[2]),
+                (alpha.clamp(0., 1.) * 255.).round() as u8,
+            ])
+        })
+    }
+}
+
+/**
+    Options used by [`crate::WordCloud::render_to_image_with`] and
+    [`crate::WordCloud::export_png_with`].
+
+    ```no_run
+    use wordcloud::{ExportOptions, RasterOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = RasterOptions::new()
+        .supersampling(2)
+        .export_options(ExportOptions::new().margin(20.));
+
+    let png = wc.export_png_with(2., &options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct RasterOptions {
+    supersampling: u32,
+    pub(crate) export_options: ExportOptions,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions {
+            supersampling: 1,
+            export_options: ExportOptions::default(),
+        }
+    }
+}
+
+impl RasterOptions {
+    /**
+        Construct the default [`RasterOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Renders the image with `factor` times the resolution in each direction and scales it
+        down afterwards, which smooths the edges even further. Defaults to `1`.
+    */
+    pub fn supersampling(mut self, factor: u32) -> Self {
+        self.supersampling = factor.max(1);
+        self
+    }
+
+    /**
+        [`ExportOptions`] used for the image. The background and margin are used.
+    */
+    pub fn export_options(mut self, export_options: ExportOptions) -> Self {
+        self.export_options = export_options;
+        self
+    }
+}
+
+impl<'a> WordCloud<'a> {
+    /**
+        Renders the WordCloud into an image by filling the outlines of the glyphs. The size of
+        the image is the [`crate::Dimensions`] multiplied by `scale`.
+    */
+    pub fn render_to_image(&self, scale: f32) -> RgbaImage {
+        self.render_to_image_with(scale, &RasterOptions::default())
+    }
+
+    /**
+        Like [`Self::render_to_image`], but customized with [`RasterOptions`]. Strokes and CSS
+        classes of the [`crate::WordStyle`] are ignored.
+    */
+    pub fn render_to_image_with(&self, scale: f32, options: &RasterOptions) -> RgbaImage {
+        let (width, height) = self.image_size(scale, &options.export_options);
+        self.render_tile(scale, options, (0, 0), (width, height))
+    }
+
+    /**
+        Size in pixels of the image rendered with `scale`
+    */
+    pub(crate) fn image_size(&self, scale: f32, options: &ExportOptions) -> (usize, usize) {
+        let canvas = self.canvas(options);
+        (
+            (canvas.width() * scale).round() as usize,
+            (canvas.height() * scale).round() as usize,
+        )
+    }
+
+    /**
+        Renders the part of the image of [`Self::render_to_image_with`], which starts at the
+        pixel `origin` and has the size `size`. Only the words inside of it are rasterized.
+    */
+    pub(crate) fn render_tile(
+        &self,
+        scale: f32,
+        raster_options: &RasterOptions,
+        origin: (usize, usize),
+        size: (usize, usize),
+    ) -> RgbaImage {
+        let options = &raster_options.export_options;
+        let factor = raster_options.supersampling.max(1) as usize;
+        let device_scale = scale * factor as f32;
+        let margin = options.margin;
+
+        let mut canvas = Canvas::new(size.0 * factor, size.1 * factor);
+        let (full_width, full_height) = self.image_size(scale, options);
+        canvas.fill_background(
+            &options.background,
+            (full_width * factor, full_height * factor),
+            (origin.0 * factor, origin.1 * factor),
+        );
+
+        let area = Rect {
+            min: Point {
+                x: origin.0 as f32 / scale - margin,
+                y: origin.1 as f32 / scale - margin,
+            },
+            max: Point {
+                x: (origin.0 + size.0) as f32 / scale - margin,
+                y: (origin.1 + size.1) as f32 / scale - margin,
+            },
+        };
+        let shift = Point {
+            x: (origin.0 * factor) as f32,
+            y: (origin.1 * factor) as f32,
+        };
+
+        let ct = self.ct.read();
+        let words = WordCloud::sorted_words(&ct);
+        let rank_count = WordCloud::rank_count(&words);
+
+        let masks = words
+            .par_iter()
+            .filter(|word| word.bounding_box.overlaps(&area))
+            .filter_map(|word| {
+                let style = self.style_for_word(word, rank_count);
+                let color = Canvas::premultiplied(
+                    &style.fill.unwrap_or(Color::rgb(0, 0, 0)),
+                    style.opacity.unwrap_or(1.),
+                );
+
+                let polygons = word
+                    .polygons(FLATTEN_TOLERANCE / device_scale)
+                    .into_iter()
+                    .map(|polygon| {
+                        polygon
+                            .into_iter()
+                            .map(|p| {
+                                (p + Point {
+                                    x: margin,
+                                    y: margin,
+                                }) * device_scale
+                                    - shift
+                            })
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<Point<f32>>>>();
+
+                CoverageMask::rasterize(&polygons, canvas.width, canvas.height)
+                    .map(|mask| (mask, color))
+            })
+            .collect::<Vec<(CoverageMask, [f32; 4])>>();
+
+        for (mask, color) in &masks {
+            canvas.composite(mask, color);
+        }
+
+        canvas.downsample(factor).into_image()
+    }
+
+    /**
+        Renders the WordCloud with [`Self::render_to_image`] and encodes it as PNG.
+    */
+    pub fn export_png(&self, scale: f32) -> Result<Vec<u8>, ImageError> {
+        self.export_png_with(scale, &RasterOptions::default())
+    }
+
+    /**
+        Like [`Self::export_png`], but customized with [`RasterOptions`].
+    */
+    pub fn export_png_with(
+        &self,
+        scale: f32,
+        options: &RasterOptions,
+    ) -> Result<Vec<u8>, ImageError> {
+        let mut target = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(self.render_to_image_with(scale, options))
+            .write_to(&mut target, ImageOutputFormat::Png)?;
+        Ok(target.into_inner())
+    }
+
+    /**
+        Writes the result of [`Self::export_png`] to a file.
+    */
+    pub fn export_png_to_file(&self, filename: &str, scale: f32) -> Result<(), ImageError> {
+        let png = self.export_png(scale)?;
+        std::fs::write(filename, png)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_coverage_mask() {
+    let square = vec![vec![
+        Point { x: 1.5, y: 1. },
+        Point { x: 3.5, y: 1. },
+        Point { x: 3.5, y: 3. },
+        Point { x: 1.5, y: 3. },
+    ]];
+    let mask = CoverageMask::rasterize(&square, 10, 10).unwrap();
+
+    assert_eq!((mask.x, mask.y, mask.width), (1, 1, 3));
+    let row: Vec<f32> = mask.coverage[0..3].to_vec();
+    assert_eq!(row, vec![0.5, 1., 0.5]);
+}
+
+#[test]
+fn test_render_to_image() {
+    use crate::cloud::word_cloud::{test_font_set, test_words};
+    use crate::{Dimensions, WordCloudBuilder, WordStyle};
+
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(400, 300))
+        .font(&font_set)
+        .style(|_| WordStyle {
+            fill: Some(Color::rgb(200, 0, 0)),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    wc.write_content(test_words(&[("Hill", 16), ("lid", 8)]), 10)
+        .unwrap();
+
+    let options = RasterOptions::new().export_options(
+        ExportOptions::new()
+            .margin(10.)
+            .background(Background::Color(Color::rgb(255, 255, 255))),
+    );
+    let image = wc.render_to_image_with(2., &options);
+    assert_eq!(image.dimensions(), (840, 640));
+    assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+
+    // pixels, whose surroundings are completely inside of a glyph, are covered
+    let inside = |polygons: &[Vec<Point<f32>>], p: Point<f32>| {
+        polygons
+            .iter()
+            .flat_map(|polygon| polygon.iter().zip(polygon.iter().cycle().skip(1)))
+            .filter(|(a, b)| (a.y > p.y) != (b.y > p.y))
+            .filter(|(a, b)| p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x))
+            .count()
+            % 2
+            == 1
+    };
+    let ct = wc.ct.read();
+    let mut covered = 0;
+    for entry in ct.iter() {
+        let polygons = entry.value_ref().polygons(0.05);
+        let bbox = entry.value_ref().bounding_box;
+        for y in (bbox.min.y as u32..bbox.max.y as u32).step_by(2) {
+            for x in (bbox.min.x as u32..bbox.max.x as u32).step_by(2) {
+                let center = Point {
+                    x: x as f32 + 0.5,
+                    y: y as f32 + 0.5,
+                };
+                let solid = [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.), (0., 0.)]
+                    .iter()
+                    .all(|(dx, dy)| inside(&polygons, center + Point { x: *dx, y: *dy }));
+                if solid {
+                    let pixel = image.get_pixel((x + 10) * 2 + 1, (y + 10) * 2 + 1);
+                    assert_eq!(pixel, &Rgba([200, 0, 0, 255]), "at {}, {}", x, y);
+                    covered += 1;
+                }
+            }
+        }
+    }
+    assert!(covered > 10);
+}
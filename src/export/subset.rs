@@ -0,0 +1,827 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use crate::cloud::word::Word;
+use crate::common::font::{Font, FontType};
+use crate::export::ExportOptions;
+
+/// `checkSumAdjustment` is chosen, so that the checksum of the whole font is this value
+const CHECKSUM_MAGIC: u32 = 0xB1B0AFBA;
+/// Type 2 charstring of an empty glyph
+const EMPTY_CHARSTRING: &[u8] = &[14];
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn tag(name: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*name)
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/**
+    The tables of an OpenType font, with their tag as key
+*/
+pub(crate) struct Sfnt {
+    pub(crate) version: u32,
+    pub(crate) tables: BTreeMap<u32, Vec<u8>>,
+}
+
+impl Sfnt {
+    /**
+        Reads the font, whose table directory starts at `offset`
+    */
+    pub(crate) fn parse(data: &[u8], offset: usize) -> Option<Self> {
+        let version = read_u32(data, offset)?;
+        let num_tables = read_u16(data, offset + 4)? as usize;
+
+        let mut tables = BTreeMap::new();
+        for i in 0..num_tables {
+            let record = offset + 12 + 16 * i;
+            let start = read_u32(data, record + 8)? as usize;
+            let length = read_u32(data, record + 12)? as usize;
+            tables.insert(
+                read_u32(data, record)?,
+                data.get(start..start + length)?.to_vec(),
+            );
+        }
+
+        Some(Sfnt { version, tables })
+    }
+
+    pub(crate) fn table(&self, name: &[u8; 4]) -> Option<&[u8]> {
+        self.tables.get(&tag(name)).map(|t| t.as_slice())
+    }
+
+    /**
+        Serializes the font with recalculated checksums
+    */
+    pub(crate) fn write(&self) -> Vec<u8> {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(name, data)| {
+                let mut data = data.clone();
+                if *name == tag(b"head") && data.len() >= 12 {
+                    data[8..12].copy_from_slice(&[0; 4]);
+                }
+                (*name, data)
+            })
+            .collect::<Vec<(u32, Vec<u8>)>>();
+
+        let num_tables = tables.len() as u16;
+        let entry_selector = 15 - num_tables.max(1).leading_zeros() as u16;
+        let search_range: u16 = (1 << entry_selector) * 16;
+
+        let mut font = Vec::new();
+        font.extend_from_slice(&self.version.to_be_bytes());
+        font.extend_from_slice(&num_tables.to_be_bytes());
+        font.extend_from_slice(&search_range.to_be_bytes());
+        font.extend_from_slice(&entry_selector.to_be_bytes());
+        font.extend_from_slice(&(num_tables * 16 - search_range).to_be_bytes());
+
+        let mut offset = 12 + 16 * tables.len();
+        let mut head_offset = None;
+        for (name, data) in &tables {
+            if *name == tag(b"head") && data.len() >= 12 {
+                head_offset = Some(offset);
+            }
+            font.extend_from_slice(&name.to_be_bytes());
+            font.extend_from_slice(&checksum(data).to_be_bytes());
+            font.extend_from_slice(&(offset as u32).to_be_bytes());
+            font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            offset += (data.len() + 3) & !3;
+        }
+
+        for (_, data) in &tables {
+            font.extend_from_slice(data);
+            font.resize((font.len() + 3) & !3, 0);
+        }
+
+        if let Some(head) = head_offset {
+            let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&font));
+            font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+        }
+
+        font
+    }
+
+    pub(crate) fn is_cff(&self) -> bool {
+        self.version == tag(b"OTTO")
+    }
+}
+
+/**
+    Removes the outlines of all glyphs, which are not in `glyphs`. The glyph ids stay the same,
+    so all other tables remain valid. Supports fonts with `glyf` and `CFF ` outlines, returns
+    `None` for all other fonts and if the font could not be read.
+*/
+pub(crate) fn subset_font(data: &[u8], offset: usize, glyphs: &BTreeSet<u16>) -> Option<Sfnt> {
+    let mut font = Sfnt::parse(data, offset)?;
+
+    if font.table(b"glyf").is_some() {
+        let long_offsets = read_u16(font.table(b"head")?, 50)? == 1;
+        let num_glyphs = read_u16(font.table(b"maxp")?, 4)? as usize;
+        let (glyf, loca) = subset_glyf(
+            font.table(b"glyf")?,
+            font.table(b"loca")?,
+            num_glyphs,
+            long_offsets,
+            glyphs,
+        )?;
+        font.tables.insert(tag(b"glyf"), glyf);
+        font.tables.insert(tag(b"loca"), loca);
+    } else if font.table(b"CFF ").is_some() {
+        let cff = subset_cff(font.table(b"CFF ")?, glyphs)?;
+        font.tables.insert(tag(b"CFF "), cff);
+    } else {
+        return None;
+    }
+
+    Some(font)
+}
+
+fn subset_glyf(
+    glyf: &[u8],
+    loca: &[u8],
+    num_glyphs: usize,
+    long_offsets: bool,
+    glyphs: &BTreeSet<u16>,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    let location = |glyph: usize| -> Option<usize> {
+        if long_offsets {
+            read_u32(loca, glyph * 4).map(|o| o as usize)
+        } else {
+            read_u16(loca, glyph * 2).map(|o| o as usize * 2)
+        }
+    };
+    let glyph_data =
+        |glyph: usize| -> Option<&[u8]> { glyf.get(location(glyph)?..location(glyph + 1)?) };
+
+    // composite glyphs need the glyphs they are made of
+    let mut keep = BTreeSet::new();
+    let mut queue = glyphs.iter().copied().chain([0]).collect::<Vec<u16>>();
+    while let Some(glyph) = queue.pop() {
+        if glyph as usize >= num_glyphs || !keep.insert(glyph) {
+            continue;
+        }
+        let data = glyph_data(glyph as usize)?;
+        if data.len() >= 10 && (read_u16(data, 0)? as i16) < 0 {
+            let mut at = 10;
+            loop {
+                let flags = read_u16(data, at)?;
+                queue.push(read_u16(data, at + 2)?);
+                at += 4 + if flags & 0x0001 != 0 { 4 } else { 2 };
+                at += match flags {
+                    f if f & 0x0008 != 0 => 2,
+                    f if f & 0x0040 != 0 => 4,
+                    f if f & 0x0080 != 0 => 8,
+                    _ => 0,
+                };
+                if flags & 0x0020 == 0 {
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut new_glyf = Vec::with_capacity(glyf.len());
+    let mut new_loca = Vec::with_capacity(loca.len());
+    for glyph in 0..=num_glyphs {
+        if long_offsets {
+            new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+        } else {
+            new_loca.extend_from_slice(&((new_glyf.len() / 2) as u16).to_be_bytes());
+        }
+        if glyph < num_glyphs && keep.contains(&(glyph as u16)) {
+            new_glyf.extend_from_slice(glyph_data(glyph)?);
+            new_glyf.resize((new_glyf.len() + 1) & !1, 0);
+        }
+    }
+
+    Some((new_glyf, new_loca))
+}
+
+/**
+    Position of an INDEX in a CFF table and the ranges of its entries
+*/
+struct CffIndex {
+    end: usize,
+    entries: Vec<Range<usize>>,
+}
+
+impl CffIndex {
+    fn parse(data: &[u8], start: usize) -> Option<Self> {
+        let count = read_u16(data, start)? as usize;
+        if count == 0 {
+            return Some(CffIndex {
+                end: start + 2,
+                entries: Vec::new(),
+            });
+        }
+
+        let offset_size = *data.get(start + 2)? as usize;
+        let read_offset = |i: usize| -> Option<usize> {
+            let at = start + 3 + i * offset_size;
+            Some(
+                data.get(at..at + offset_size)?
+                    .iter()
+                    .fold(0, |o, b| o << 8 | *b as usize),
+            )
+        };
+        // offsets are relative to the byte before the data
+        let base = start + 2 + (count + 1) * offset_size;
+        let entries = (0..count)
+            .map(|i| Some(base + read_offset(i)?..base + read_offset(i + 1)?))
+            .collect::<Option<Vec<Range<usize>>>>()?;
+
+        Some(CffIndex {
+            end: base + read_offset(count)?,
+            entries,
+        })
+    }
+
+    fn write(entries: &[&[u8]]) -> Vec<u8> {
+        let mut index = (entries.len() as u16).to_be_bytes().to_vec();
+        if entries.is_empty() {
+            return index;
+        }
+
+        let total = entries.iter().map(|e| e.len()).sum::<usize>() + 1;
+        let offset_size = match total {
+            t if t <= 0xFF => 1,
+            t if t <= 0xFFFF => 2,
+            t if t <= 0xFF_FFFF => 3,
+            _ => 4,
+        };
+        index.push(offset_size as u8);
+
+        let mut offset = 1;
+        for entry in entries.iter().map(|e| e.len()).chain([0]) {
+            index.extend_from_slice(&(offset as u32).to_be_bytes()[4 - offset_size..]);
+            offset += entry;
+        }
+        for entry in entries {
+            index.extend_from_slice(entry);
+        }
+        index
+    }
+}
+
+/// Integer operands of a CFF DICT by their operator, with their position in the table
+type CffDict = BTreeMap<u16, Vec<(i32, Range<usize>)>>;
+
+fn parse_cff_dict(data: &[u8], range: Range<usize>) -> Option<CffDict> {
+    let mut operators = BTreeMap::new();
+    let mut operands = Vec::new();
+    let mut at = range.start;
+
+    while at < range.end {
+        let b0 = *data.get(at)?;
+        let (value, length) = match b0 {
+            0..=21 => {
+                let (operator, length) = match b0 {
+                    12 => (1200 + *data.get(at + 1)? as u16, 2),
+                    _ => (b0 as u16, 1),
+                };
+                operators.insert(operator, std::mem::take(&mut operands));
+                at += length;
+                continue;
+            }
+            28 => (read_u16(data, at + 1)? as i16 as i32, 3),
+            29 => (read_u32(data, at + 1)? as i32, 5),
+            30 => {
+                // real numbers are never offsets, only their length matters
+                let mut end = at + 1;
+                while data.get(end)? & 0x0F != 0x0F && data.get(end)? & 0xF0 != 0xF0 {
+                    end += 1;
+                }
+                (0, end + 1 - at)
+            }
+            32..=246 => (b0 as i32 - 139, 1),
+            247..=250 => ((b0 as i32 - 247) * 256 + *data.get(at + 1)? as i32 + 108, 2),
+            251..=254 => (
+                -(b0 as i32 - 251) * 256 - *data.get(at + 1)? as i32 - 108,
+                2,
+            ),
+            _ => return None,
+        };
+        operands.push((value, at..at + length));
+        at += length;
+    }
+
+    Some(operators)
+}
+
+/**
+    Replaces an integer operand of a DICT without changing its length
+*/
+fn rewrite_cff_operand(data: &mut [u8], range: &Range<usize>, value: i32) -> Option<()> {
+    match range.len() {
+        1 if (-107..=107).contains(&value) => data[range.start] = (value + 139) as u8,
+        2 if (108..=1131).contains(&value) => {
+            data[range.start] = ((value - 108) / 256 + 247) as u8;
+            data[range.start + 1] = ((value - 108) % 256) as u8;
+        }
+        3 if (i16::MIN as i32..=i16::MAX as i32).contains(&value) => {
+            data[range.start + 1..range.end].copy_from_slice(&(value as i16).to_be_bytes())
+        }
+        5 => data[range.start + 1..range.end].copy_from_slice(&value.to_be_bytes()),
+        _ => return None,
+    }
+    Some(())
+}
+
+fn subset_cff(cff: &[u8], glyphs: &BTreeSet<u16>) -> Option<Vec<u8>> {
+    const CHARSET: u16 = 15;
+    const ENCODING: u16 = 16;
+    const CHAR_STRINGS: u16 = 17;
+    const PRIVATE: u16 = 18;
+    const SUBRS: u16 = 19;
+    const FD_ARRAY: u16 = 1236;
+    const FD_SELECT: u16 = 1237;
+
+    let names = CffIndex::parse(cff, *cff.get(2)? as usize)?;
+    let top_dicts = CffIndex::parse(cff, names.end)?;
+    let top_dict = parse_cff_dict(cff, top_dicts.entries.first()?.clone())?;
+
+    let operand = |dict: &CffDict, operator: u16, i: usize| {
+        dict.get(&operator)
+            .and_then(|o| o.get(i))
+            .map(|(value, range)| (*value as usize, range.clone()))
+    };
+
+    let (char_strings_offset, _) = operand(&top_dict, CHAR_STRINGS, 0)?;
+    let char_strings = CffIndex::parse(cff, char_strings_offset)?;
+    let new_char_strings = CffIndex::write(
+        &char_strings
+            .entries
+            .iter()
+            .enumerate()
+            .map(
+                |(glyph, range)| match glyph == 0 || glyphs.contains(&(glyph as u16)) {
+                    true => &cff[range.clone()],
+                    false => EMPTY_CHARSTRING,
+                },
+            )
+            .collect::<Vec<&[u8]>>(),
+    );
+
+    // everything behind the CharStrings moves, all offsets pointing there have to be updated.
+    // Empty CharStrings grow, fonts with too many of them are embedded without subsetting.
+    let removed = (char_strings.end - char_strings_offset).checked_sub(new_char_strings.len())?;
+    let moved = |offset: usize| -> Option<usize> {
+        match offset {
+            o if o >= char_strings.end => Some(o - removed),
+            o if o <= char_strings_offset => Some(o),
+            _ => None,
+        }
+    };
+
+    let mut old = cff.to_vec();
+    let mut rewrite = |range: &Range<usize>, offset: usize| -> Option<()> {
+        rewrite_cff_operand(&mut old, range, moved(offset)? as i32)
+    };
+
+    for (operator, predefined) in [(CHARSET, 2), (ENCODING, 1), (FD_ARRAY, 0), (FD_SELECT, 0)] {
+        if let Some((offset, range)) = operand(&top_dict, operator, 0) {
+            if offset > predefined {
+                rewrite(&range, offset)?;
+            }
+        }
+    }
+
+    let mut private_dicts = Vec::new();
+    if let (Some((size, _)), Some((offset, range))) = (
+        operand(&top_dict, PRIVATE, 0),
+        operand(&top_dict, PRIVATE, 1),
+    ) {
+        rewrite(&range, offset)?;
+        private_dicts.push(offset..offset + size);
+    }
+    if let Some((fd_array_offset, _)) = operand(&top_dict, FD_ARRAY, 0) {
+        for font_dict in CffIndex::parse(cff, fd_array_offset)?.entries {
+            let font_dict = parse_cff_dict(cff, font_dict)?;
+            if let (Some((size, _)), Some((offset, range))) = (
+                operand(&font_dict, PRIVATE, 0),
+                operand(&font_dict, PRIVATE, 1),
+            ) {
+                rewrite(&range, offset)?;
+                private_dicts.push(offset..offset + size);
+            }
+        }
+    }
+
+    // local subroutines are addressed relative to their private DICT
+    for private in private_dicts {
+        let private_dict = parse_cff_dict(cff, private.clone())?;
+        if let Some((offset, range)) = operand(&private_dict, SUBRS, 0) {
+            let relative = moved(private.start + offset)? - moved(private.start)?;
+            rewrite_cff_operand(&mut old, &range, relative as i32)?;
+        }
+    }
+
+    let mut subset = old[..char_strings_offset].to_vec();
+    subset.extend_from_slice(&new_char_strings);
+    subset.extend_from_slice(&old[char_strings.end..]);
+    Some(subset)
+}
+
+/**
+    Data of the font with its table directory at `offset`. Faces of a collection are extracted
+    into a font of their own, because a collection can't be embedded.
+*/
+pub(crate) fn face_data(data: &[u8], offset: usize) -> Cow<'_, [u8]> {
+    match offset {
+        0 => Cow::Borrowed(data),
+        _ => match Sfnt::parse(data, offset) {
+            Some(sfnt) => Cow::Owned(sfnt.write()),
+            None => Cow::Borrowed(data),
+        },
+    }
+}
+
+/**
+    Font data to embed into an SVG for the runs of the `words` in `font` and the type of the
+    data. Depending on the [`ExportOptions`], the font is subset and compressed.
+*/
+pub(crate) fn embedded_font<'f>(
+    font: &'f Font,
+    words: &[&Word],
+    options: &ExportOptions,
+) -> (Cow<'f, [u8]>, &'static str) {
+    let reference = font.reference();
+    let subset = match options.subset_fonts {
+        false => None,
+        true => {
+            let charmap = reference.charmap();
+            let glyphs = words
+                .iter()
+                .flat_map(|w| {
+                    w.runs
+                        .iter()
+                        .filter(|r| r.font == font)
+                        .map(move |r| (w, r))
+                })
+                .flat_map(|(w, r)| {
+                    w.glyphs[r.glyphs.clone()]
+                        .iter()
+                        .map(|l| l.glyph_id)
+                        .chain(w.text[r.text.clone()].chars().map(|c| charmap.map(c)))
+                })
+                .collect::<BTreeSet<u16>>();
+            subset_font(reference.data, reference.offset as usize, &glyphs)
+        }
+    };
+
+    match subset {
+        None => match font.packed() {
+            None => (
+                face_data(reference.data, reference.offset as usize),
+                font.font_type().embed_tag(),
+            ),
+            Some(packed) => (
+                Cow::Borrowed(packed.as_slice()),
+                font.font_type().embed_tag(),
+            ),
+        },
+        Some(subset) => {
+            #[cfg(feature = "woff2")]
+            if options.compress_fonts {
+                if let Some(compressed) = woff2::encode(&subset) {
+                    return (Cow::Owned(compressed), FontType::WOFF2.embed_tag());
+                }
+            }
+            let font_type = if subset.is_cff() {
+                FontType::OTF
+            } else {
+                FontType::TTF
+            };
+            (Cow::Owned(subset.write()), font_type.embed_tag())
+        }
+    }
+}
+
+#[cfg(feature = "woff2")]
+pub(crate) mod woff2 {
+    use std::io::Write;
+
+    use crate::export::subset::{tag, Sfnt};
+
+    /// Tags, which are referenced by their index in the table directory
+    pub(super) const KNOWN_TAGS: [&[u8; 4]; 63] = [
+        b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+        b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+        b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+        b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+        b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+        b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+        b"Gloc", b"Feat", b"Sill",
+    ];
+    /// Transformation version of `glyf` and `loca`, which stores them unchanged
+    const NULL_TRANSFORM: u8 = 3;
+    const BROTLI_QUALITY: u32 = 11;
+    const BROTLI_WINDOW: u32 = 22;
+
+    fn write_base128(target: &mut Vec<u8>, value: u32) {
+        let bytes = (0..5)
+            .rev()
+            .map(|i| (value >> (7 * i)) as u8 & 0x7F)
+            .skip_while(|b| *b == 0)
+            .collect::<Vec<u8>>();
+        if bytes.is_empty() {
+            target.push(0);
+        }
+        for (i, byte) in bytes.iter().enumerate() {
+            target.push(if i + 1 < bytes.len() {
+                byte | 0x80
+            } else {
+                *byte
+            });
+        }
+    }
+
+    /**
+        Compresses the font as WOFF2, without the optional transformations of the tables
+    */
+    pub(crate) fn encode(font: &Sfnt) -> Option<Vec<u8>> {
+        // loca has to follow glyf directly, otherwise the order doesn't matter
+        let mut tables = font.tables.iter().collect::<Vec<(&u32, &Vec<u8>)>>();
+        tables.sort_by_key(|(name, _)| match **name == tag(b"loca") {
+            true => tag(b"glyf"),
+            false => **name,
+        });
+
+        let mut directory = Vec::new();
+        let mut stream = Vec::new();
+        for (name, data) in tables {
+            let known = KNOWN_TAGS.iter().position(|t| tag(t) == *name);
+            let transform = if *name == tag(b"glyf") || *name == tag(b"loca") {
+                NULL_TRANSFORM << 6
+            } else {
+                0
+            };
+            directory.push(known.unwrap_or(63) as u8 | transform);
+            if known.is_none() {
+                directory.extend_from_slice(&name.to_be_bytes());
+            }
+            write_base128(&mut directory, data.len() as u32);
+            stream.extend_from_slice(data);
+        }
+
+        let mut compressed = Vec::new();
+        {
+            let mut writer =
+                brotli::CompressorWriter::new(&mut compressed, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+            writer.write_all(&stream).ok()?;
+        }
+
+        let sfnt_size = 12
+            + 16 * font.tables.len()
+            + font
+                .tables
+                .values()
+                .map(|t| (t.len() + 3) & !3)
+                .sum::<usize>();
+        let length = (48 + directory.len() + compressed.len() + 3) & !3;
+
+        let mut woff2 = Vec::with_capacity(length);
+        woff2.extend_from_slice(b"wOF2");
+        woff2.extend_from_slice(&font.version.to_be_bytes());
+        woff2.extend_from_slice(&(length as u32).to_be_bytes());
+        woff2.extend_from_slice(&(font.tables.len() as u16).to_be_bytes());
+        woff2.extend_from_slice(&[0; 2]);
+        woff2.extend_from_slice(&(sfnt_size as u32).to_be_bytes());
+        woff2.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+        woff2.extend_from_slice(&[0, 1, 0, 0]);
+        // no metadata and private data blocks
+        woff2.extend_from_slice(&[0; 20]);
+        woff2.extend_from_slice(&directory);
+        woff2.extend_from_slice(&compressed);
+        woff2.resize(length, 0);
+
+        Some(woff2)
+    }
+}
+
+#[test]
+fn test_subset_glyf() {
+    let font = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    let reference = swash::FontRef::from_index(font, 0).unwrap();
+    let (a, z) = (reference.charmap().map('a'), reference.charmap().map('z'));
+
+    let parsed = subset_font(font, 0, &BTreeSet::from([a])).unwrap();
+    let subset = parsed.write();
+    assert!(subset.len() < font.len() / 2);
+    assert_eq!(checksum(&subset), CHECKSUM_MAGIC);
+
+    let subset_reference = swash::FontRef::from_index(&subset, 0).unwrap();
+    let mut context = swash::scale::ScaleContext::new();
+    let mut scaler = context.builder(subset_reference).size(20.).build();
+    assert!(!scaler.scale_outline(a).unwrap().points().is_empty());
+    assert!(scaler.scale_outline(z).unwrap().points().is_empty());
+
+    #[cfg(feature = "woff2")]
+    {
+        let encoded = woff2::encode(&parsed).unwrap();
+        assert_eq!(&encoded[0..4], b"wOF2");
+        assert_eq!(encoded.len() % 4, 0);
+
+        let compressed_size = read_u32(&encoded, 20).unwrap() as usize;
+        let directory_end =
+            encoded.len() - compressed_size - (encoded.len() - 48 - compressed_size) % 4;
+        let mut stream = Vec::new();
+        brotli::BrotliDecompress(&mut &encoded[directory_end..], &mut stream).unwrap();
+
+        let (mut at, mut position) = (48, 0);
+        let mut names = Vec::new();
+        while at < directory_end {
+            let name = match (encoded[at] & 0x3F) as usize {
+                63 => {
+                    at += 4;
+                    encoded[at - 3..at + 1].try_into().unwrap()
+                }
+                known => *woff2::KNOWN_TAGS[known],
+            };
+            let mut length = 0;
+            loop {
+                at += 1;
+                length = length << 7 | (encoded[at] & 0x7F) as usize;
+                if encoded[at] & 0x80 == 0 {
+                    break;
+                }
+            }
+            at += 1;
+            assert_eq!(
+                &stream[position..position + length],
+                parsed.table(&name).unwrap()
+            );
+            position += length;
+            names.push(name);
+        }
+        assert_eq!(names.len(), parsed.tables.len());
+        let glyf = names.iter().position(|n| n == b"glyf").unwrap();
+        assert_eq!(&names[glyf + 1], b"loca");
+    }
+}
+
+#[cfg(feature = "woff2")]
+#[test]
+fn test_woff2_round_trip() {
+    let font = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    let reference = swash::FontRef::from_index(font, 0).unwrap();
+    let glyphs = "Round trip"
+        .chars()
+        .map(|c| reference.charmap().map(c))
+        .collect();
+    let parsed = subset_font(font, 0, &glyphs).unwrap();
+
+    let encoded = woff2::encode(&parsed).unwrap();
+    assert_eq!(read_u32(&encoded, 8), Some(encoded.len() as u32));
+    assert_eq!(read_u16(&encoded, 12), Some(parsed.tables.len() as u16));
+
+    // the table directory, the tags of unknown tables follow their flags
+    let (mut at, mut directory) = (48, Vec::new());
+    for _ in 0..parsed.tables.len() {
+        let name = match (encoded[at] & 0x3F) as usize {
+            63 => read_u32(&encoded, at + 1).unwrap(),
+            known => tag(woff2::KNOWN_TAGS[known]),
+        };
+        at += if encoded[at] & 0x3F == 63 { 5 } else { 1 };
+        let mut length = 0;
+        while encoded[at] & 0x80 != 0 {
+            length = length << 7 | (encoded[at] & 0x7F) as usize;
+            at += 1;
+        }
+        directory.push((name, length << 7 | encoded[at] as usize));
+        at += 1;
+    }
+
+    // the compressed stream is only followed by the zeros padding the file to 4 bytes
+    let stream = at..at + read_u32(&encoded, 20).unwrap() as usize;
+    assert_eq!(encoded.len() % 4, 0);
+    assert!(encoded.len() - stream.end < 4);
+    assert!(encoded[stream.end..].iter().all(|b| *b == 0));
+
+    let mut decompressed = Vec::new();
+    brotli::BrotliDecompress(&mut &encoded[stream], &mut decompressed).unwrap();
+    let mut round_trip = Sfnt {
+        version: read_u32(&encoded, 4).unwrap(),
+        tables: BTreeMap::new(),
+    };
+    let mut position = 0;
+    for (name, length) in directory {
+        round_trip
+            .tables
+            .insert(name, decompressed[position..position + length].to_vec());
+        position += length;
+    }
+    assert_eq!(position, decompressed.len());
+    assert_eq!(round_trip.version, parsed.version);
+    assert_eq!(round_trip.tables, parsed.tables);
+
+    let decoded = round_trip.write();
+    assert_eq!(read_u32(&encoded, 16), Some(decoded.len() as u32));
+    let decoded_reference = swash::FontRef::from_index(&decoded, 0).unwrap();
+    let mut context = swash::scale::ScaleContext::new();
+    let mut scaler = context.builder(decoded_reference).size(20.).build();
+    assert!(!scaler
+        .scale_outline(reference.charmap().map('R'))
+        .unwrap()
+        .points()
+        .is_empty());
+}
+
+#[test]
+fn test_subset_cff() {
+    // CharStrings followed by a private DICT with local subroutines, the CharStrings start
+    // behind the header, the name INDEX, the top DICT INDEX, the string and global subrs INDEX
+    let cff_with = |char_strings: &[&[u8]]| {
+        let char_strings = CffIndex::write(char_strings);
+        let char_strings_offset = 4 + 2 + (5 + 13) + 2 + 2;
+        let private_offset = char_strings_offset + char_strings.len();
+        let top_dict = [
+            &[29][..],
+            &(char_strings_offset as i32).to_be_bytes(),
+            &[17, 139 + 2, 29],
+            &(private_offset as i32).to_be_bytes(),
+            &[18],
+        ]
+        .concat();
+        assert_eq!(top_dict.len(), 13);
+        let mut cff = vec![1, 0, 4, 1];
+        cff.extend_from_slice(&CffIndex::write(&[]));
+        cff.extend_from_slice(&CffIndex::write(&[&top_dict]));
+        cff.extend_from_slice(&CffIndex::write(&[]));
+        cff.extend_from_slice(&CffIndex::write(&[]));
+        cff.extend_from_slice(&char_strings);
+        cff.extend_from_slice(&[139 + 2, 19]);
+        cff.extend_from_slice(&CffIndex::write(&[&[11]]));
+        cff
+    };
+    let cff = cff_with(&[&[14], &[1, 2, 3, 14], &[4, 5, 6, 14]]);
+
+    let subset = subset_cff(&cff, &BTreeSet::from([2])).unwrap();
+    assert_eq!(subset.len(), cff.len() - 3);
+
+    let top_dicts = CffIndex::parse(&subset, 4 + 2).unwrap();
+    let top_dict = parse_cff_dict(&subset, top_dicts.entries[0].clone()).unwrap();
+    let char_strings = CffIndex::parse(&subset, top_dict[&17][0].0 as usize).unwrap();
+    let charstring = |i: usize| &subset[char_strings.entries[i].clone()];
+    assert_eq!(charstring(1), EMPTY_CHARSTRING);
+    assert_eq!(charstring(2), &[4, 5, 6, 14]);
+
+    let private = top_dict[&18][1].0 as usize;
+    assert_eq!(private, char_strings.end);
+    assert_eq!(&subset[private..private + 2], &[139 + 2, 19]);
+
+    // replacing empty CharStrings would make the INDEX larger, the font isn't subset
+    assert!(subset_cff(&cff_with(&[&[14], &[], &[]]), &BTreeSet::new()).is_none());
+}
+
+#[test]
+fn test_collection_face() {
+    // a collection with Open Sans as its only face, moved behind the collection header
+    let font = include_bytes!("../../example/assets/OpenSans-Regular.ttf");
+    let offset = 16;
+    let mut collection = [
+        b"ttcf".as_slice(),
+        &[0, 1, 0, 0, 0, 0, 0, 1],
+        &[0, 0, 0, offset as u8],
+    ]
+    .concat();
+    collection.extend_from_slice(font);
+    for i in 0..read_u16(font, 4).unwrap() as usize {
+        let record = offset + 12 + 16 * i + 8;
+        let start = read_u32(&collection, record).unwrap() + offset as u32;
+        collection[record..record + 4].copy_from_slice(&start.to_be_bytes());
+    }
+
+    assert_eq!(
+        Font::collection_faces(&collection),
+        vec!["Open Sans Regular"]
+    );
+    let face = Font::from_collection(&collection, 0).unwrap();
+    assert_eq!(face.family(), "Open Sans");
+    assert!(Font::from_collection(&collection, 1).is_err());
+
+    // the extracted face is a font of its own again
+    let (extracted, original) = (
+        Sfnt::parse(&face_data(&collection, offset), 0).unwrap(),
+        Sfnt::parse(font, 0).unwrap(),
+    );
+    assert!(original.tables.keys().eq(extracted.tables.keys()));
+    assert_eq!(extracted.table(b"glyf"), original.table(b"glyf"));
+}
@@ -0,0 +1,153 @@
+use std::fmt::Display;
+use std::io::{Error, Write};
+
+use svg::node::element::Element;
+
+use crate::export::{escape_xml, XmlFormatting};
+
+/**
+    Writes an SVG document element by element, so it never has to be held in memory completely.
+    Container elements are opened with [`Self::start`] and closed with [`Self::end`], everything
+    in between is written with [`Self::node`].
+*/
+pub(crate) struct SvgWriter<W: Write> {
+    target: W,
+    formatting: XmlFormatting,
+    open: Vec<String>,
+}
+
+impl<W: Write> SvgWriter<W> {
+    pub(crate) fn new(target: W, formatting: XmlFormatting) -> Self {
+        SvgWriter {
+            target,
+            formatting,
+            open: Vec::new(),
+        }
+    }
+
+    fn separator(&mut self) -> Result<(), Error> {
+        match self.formatting {
+            XmlFormatting::Compact => Ok(()),
+            XmlFormatting::Newlines => self.target.write_all(b"\n"),
+            XmlFormatting::Indented(width) => {
+                write!(self.target, "\n{:1$}", "", width * self.open.len())
+            }
+        }
+    }
+
+    /**
+        Opens the element, without writing its children. Unlike the attributes of nodes, which
+        are written as they are, the attribute values are escaped here.
+    */
+    pub(crate) fn start(&mut self, element: &Element) -> Result<(), Error> {
+        if !self.open.is_empty() {
+            self.separator()?;
+        }
+
+        write!(self.target, "<{}", element.get_name())?;
+        let mut attributes = element.get_attributes().iter().collect::<Vec<_>>();
+        attributes.sort_by_key(|pair| pair.0.as_str());
+        for (name, value) in attributes {
+            write!(self.target, r#" {}="{}""#, name, escape_xml(value))?;
+        }
+        self.target.write_all(b">")?;
+
+        self.open.push(element.get_name().clone());
+        Ok(())
+    }
+
+    /**
+        Writes a complete node including its children
+    */
+    pub(crate) fn node(&mut self, node: &impl Display) -> Result<(), Error> {
+        self.separator()?;
+
+        let serialized = node.to_string();
+        match self.formatting {
+            XmlFormatting::Compact => self.target.write_all(compact(&serialized).as_bytes()),
+            XmlFormatting::Newlines => self.target.write_all(serialized.as_bytes()),
+            XmlFormatting::Indented(width) => {
+                let mut depth = self.open.len();
+                for (i, line) in serialized.lines().enumerate() {
+                    if line.starts_with("</") {
+                        depth = depth.saturating_sub(1);
+                    }
+                    if i > 0 {
+                        write!(self.target, "\n{:1$}", "", width * depth)?;
+                    }
+                    self.target.write_all(line.as_bytes())?;
+                    if line.starts_with('<')
+                        && !line.starts_with("</")
+                        && !line.ends_with("/>")
+                        && !line.contains("</")
+                    {
+                        depth += 1;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /**
+        Closes the most recently opened element
+    */
+    pub(crate) fn end(&mut self) -> Result<(), Error> {
+        if let Some(name) = self.open.pop() {
+            self.separator()?;
+            write!(self.target, "</{}>", name)?;
+        }
+        Ok(())
+    }
+
+    /**
+        Closes all open elements and returns the target
+    */
+    pub(crate) fn finish(mut self) -> Result<W, Error> {
+        while !self.open.is_empty() {
+            self.end()?;
+        }
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+
+/**
+    Removes the line breaks, which the svg crate writes between tags and around the content of
+    elements. Line breaks inside text, style sheets and other content are kept.
+*/
+fn compact(serialized: &str) -> String {
+    let bytes = serialized.as_bytes();
+    serialized
+        .char_indices()
+        .filter(|(i, c)| {
+            *c != '\n'
+                || !(i.checked_sub(1).and_then(|p| bytes.get(p)) == Some(&b'>')
+                    || bytes.get(i + 1) == Some(&b'<'))
+        })
+        .map(|(_, c)| c)
+        .collect()
+}
+
+#[test]
+fn test_svg_writer() {
+    use svg::node::element::{Group, Style, Text};
+
+    let mut writer = SvgWriter::new(Vec::new(), XmlFormatting::Compact);
+    writer
+        .start(&Group::new().set("aria-label", r#"say "it's" <b>&"#))
+        .unwrap();
+    writer
+        .node(&Text::new().add(svg::node::Text::new("a\nb")))
+        .unwrap();
+    writer
+        .node(&Style::new(".word {\n  fill: red;\n}"))
+        .unwrap();
+    let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    assert_eq!(
+        written,
+        r#"<g aria-label="say &quot;it&apos;s&quot; &lt;b&gt;&amp;"><text>a"#.to_string()
+            + "\nb</text><style>.word {\n  fill: red;\n}</style></g>"
+    );
+}
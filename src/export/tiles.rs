@@ -0,0 +1,462 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+#[cfg(feature = "raster")]
+use crate::export::raster::RasterOptions;
+use crate::export::{format_number, RenderedOptions};
+use crate::types::point::Point;
+use crate::types::rect::Rect;
+use crate::WordCloud;
+
+/**
+    File format of the tiles written by [`crate::WordCloud::export_tiles`].
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileFormat {
+    /// SVG with the words rendered as paths, like [`crate::WordCloud::export_rendered`].
+    #[default]
+    Svg,
+    /// PNG rendered with `scale`, like [`crate::WordCloud::export_png`].
+    #[cfg(feature = "raster")]
+    Png { scale: f32 },
+}
+
+/**
+    Options used by [`crate::WordCloud::export_tiles`].
+
+    ```no_run
+    use wordcloud::{ExportOptions, RenderedOptions, TileFormat, TileOptions};
+    # use wordcloud::font::{Font, FontSetBuilder};
+    # use wordcloud::{Dimensions, WordCloudBuilder};
+    #
+    # let mut font_data = std::fs::read("font.ttf").unwrap();
+    # let font_set = FontSetBuilder::new().push(Font::from_data(&mut font_data).unwrap()).build().unwrap();
+    # let wc = WordCloudBuilder::new().dimensions(Dimensions::from_wh(800, 600)).font(&font_set).build().unwrap();
+
+    let options = TileOptions::new()
+        .grid(8, 6)
+        .format(TileFormat::Svg)
+        .rendered_options(RenderedOptions::new().export_options(ExportOptions::new().precision(1)));
+
+    wc.export_tiles("poster", &options).unwrap();
+    ```
+*/
+#[derive(Clone, Debug)]
+pub struct TileOptions {
+    columns: usize,
+    rows: usize,
+    format: TileFormat,
+    rendered_options: RenderedOptions,
+    #[cfg(feature = "raster")]
+    raster_options: RasterOptions,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        TileOptions {
+            columns: 2,
+            rows: 2,
+            format: TileFormat::default(),
+            rendered_options: RenderedOptions::default(),
+            #[cfg(feature = "raster")]
+            raster_options: RasterOptions::default(),
+        }
+    }
+}
+
+impl TileOptions {
+    /**
+        Construct the default [`TileOptions`]
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Number of tiles in each direction, defaults to 2×2
+    */
+    pub fn grid(mut self, columns: usize, rows: usize) -> Self {
+        self.columns = columns.max(1);
+        self.rows = rows.max(1);
+        self
+    }
+
+    /**
+        [`TileFormat`] of the tiles, defaults to [`TileFormat::Svg`]
+    */
+    pub fn format(mut self, format: TileFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /**
+        [`RenderedOptions`] used for every tile with [`TileFormat::Svg`]
+    */
+    pub fn rendered_options(mut self, rendered_options: RenderedOptions) -> Self {
+        self.rendered_options = rendered_options;
+        self
+    }
+
+    /**
+        [`RasterOptions`] used for every tile with [`TileFormat::Png`]
+    */
+    #[cfg(feature = "raster")]
+    pub fn raster_options(mut self, raster_options: RasterOptions) -> Self {
+        self.raster_options = raster_options;
+        self
+    }
+}
+
+/**
+    Position of a tile in the whole WordCloud, in pixels of the SVG or of the PNG
+*/
+struct Tile {
+    column: usize,
+    row: usize,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Tile {
+    fn file_name(&self, extension: &str) -> String {
+        format!("tile_{}_{}.{}", self.row, self.column, extension)
+    }
+
+    /**
+        Splits `width`×`height` into the grid. With `whole_pixels`, the borders are rounded to
+        whole pixels, so that the tiles fit together without gaps.
+    */
+    fn grid(width: f32, height: f32, options: &TileOptions, whole_pixels: bool) -> Vec<Tile> {
+        let border = |size: f32, i: usize, count: usize| {
+            let position = size * i as f32 / count as f32;
+            if whole_pixels {
+                position.round()
+            } else {
+                position
+            }
+        };
+
+        (0..options.rows)
+            .flat_map(|row| {
+                (0..options.columns).map(move |column| {
+                    let (x, y) = (
+                        border(width, column, options.columns),
+                        border(height, row, options.rows),
+                    );
+                    Tile {
+                        column,
+                        row,
+                        x,
+                        y,
+                        width: border(width, column + 1, options.columns) - x,
+                        height: border(height, row + 1, options.rows) - y,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+impl<'a> WordCloud<'a> {
+    /**
+        Splits the WordCloud into a grid of tiles and writes every tile as its own file into
+        `directory`, e.g. `tile_0_1.svg` for the second tile of the first row. Words crossing
+        the border of a tile are part of both tiles and clipped at the border.
+
+        A `manifest.json` describes the grid: the size of the whole WordCloud and the file and
+        position of every tile, all in pixels.
+    */
+    pub fn export_tiles<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        options: &TileOptions,
+    ) -> Result<(), Error> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        let (format, extension, width, height, tiles, precision) = match options.format {
+            TileFormat::Svg => {
+                let export_options = &options.rendered_options.export_options;
+                let canvas = self.canvas(export_options);
+                let tiles = Tile::grid(canvas.width(), canvas.height(), options, false);
+                for tile in &tiles {
+                    let area = Rect {
+                        min: canvas.min
+                            + Point {
+                                x: tile.x,
+                                y: tile.y,
+                            },
+                        max: canvas.min
+                            + Point {
+                                x: tile.x + tile.width,
+                                y: tile.y + tile.height,
+                            },
+                    };
+                    let file = BufWriter::new(File::create(directory.join(tile.file_name("svg")))?);
+                    self.write_rendered_area(file, &options.rendered_options, area)?;
+                }
+                (
+                    "svg",
+                    "svg",
+                    canvas.width(),
+                    canvas.height(),
+                    tiles,
+                    export_options.precision,
+                )
+            }
+            #[cfg(feature = "raster")]
+            TileFormat::Png { scale } => {
+                let export_options = &options.raster_options.export_options;
+                let (width, height) = self.image_size(scale, export_options);
+                let tiles = Tile::grid(width as f32, height as f32, options, true);
+                for tile in &tiles {
+                    let image = self.render_tile(
+                        scale,
+                        &options.raster_options,
+                        (tile.x as usize, tile.y as usize),
+                        (tile.width as usize, tile.height as usize),
+                    );
+                    image
+                        .save_with_format(
+                            directory.join(tile.file_name("png")),
+                            image::ImageFormat::Png,
+                        )
+                        .map_err(Error::other)?;
+                }
+                (
+                    "png",
+                    "png",
+                    width as f32,
+                    height as f32,
+                    tiles,
+                    export_options.precision,
+                )
+            }
+        };
+
+        let number = |value: f32| format_number(value, precision);
+        let mut manifest = BufWriter::new(File::create(directory.join("manifest.json"))?);
+        write!(
+            manifest,
+            "{{\n  \"format\": \"{}\",\n  \"width\": {},\n  \"height\": {},\n  \"columns\": {},\n  \"rows\": {},\n  \"tiles\": [",
+            format,
+            number(width),
+            number(height),
+            options.columns,
+            options.rows
+        )?;
+        for (i, tile) in tiles.iter().enumerate() {
+            write!(
+                manifest,
+                "{}\n    {{ \"file\": \"{}\", \"column\": {}, \"row\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {} }}",
+                if i == 0 { "" } else { "," },
+                tile.file_name(extension),
+                tile.column,
+                tile.row,
+                number(tile.x),
+                number(tile.y),
+                number(tile.width),
+                number(tile.height)
+            )?;
+        }
+        manifest.write_all(b"\n  ]\n}\n")?;
+        manifest.flush()
+    }
+}
+
+#[test]
+fn test_tile_grid() {
+    let options = TileOptions::new().grid(3, 2);
+    let tiles = Tile::grid(1000., 701., &options, true);
+
+    assert_eq!(tiles.len(), 6);
+    assert_eq!(tiles[1].file_name("png"), "tile_0_1.png");
+    // the rounded tiles cover the image without gaps or overlaps
+    assert_eq!(tiles.iter().take(3).map(|t| t.width).sum::<f32>(), 1000.);
+    assert_eq!(tiles[3].y, tiles[0].height);
+    assert_eq!(tiles[0].height + tiles[3].height, 701.);
+}
+
+/**
+    Minimal JSON value, enough to check that the manifest is valid JSON
+*/
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+enum Json {
+    Number(f32),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[cfg(test)]
+impl Json {
+    fn parse(text: &str) -> Option<Json> {
+        let (value, rest) = Json::value(text)?;
+        rest.trim().is_empty().then_some(value)
+    }
+
+    fn value(text: &str) -> Option<(Json, &str)> {
+        let text = text.trim_start();
+        match text.chars().next()? {
+            '"' => {
+                let end = text[1..].find('"')? + 1;
+                Some((Json::String(text[1..end].to_string()), &text[end + 1..]))
+            }
+            '[' => {
+                Json::list(text, ']', Json::value).map(|(items, rest)| (Json::Array(items), rest))
+            }
+            '{' => Json::list(text, '}', Json::member)
+                .map(|(members, rest)| (Json::Object(members), rest)),
+            _ => {
+                let end = text
+                    .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+                    .unwrap_or(text.len());
+                Some((Json::Number(text[..end].parse().ok()?), &text[end..]))
+            }
+        }
+    }
+
+    fn member(text: &str) -> Option<((String, Json), &str)> {
+        match Json::value(text)? {
+            (Json::String(key), rest) => {
+                let (value, rest) = Json::value(rest.trim_start().strip_prefix(':')?)?;
+                Some(((key, value), rest))
+            }
+            _ => None,
+        }
+    }
+
+    /**
+        Comma separated items between the opening bracket at the start of `text` and `end`
+    */
+    fn list<T>(
+        text: &str,
+        end: char,
+        item: fn(&str) -> Option<(T, &str)>,
+    ) -> Option<(Vec<T>, &str)> {
+        let mut items = Vec::new();
+        let mut text = text[1..].trim_start();
+        if let Some(rest) = text.strip_prefix(end) {
+            return Some((items, rest));
+        }
+        loop {
+            let (value, rest) = item(text)?;
+            items.push(value);
+            let rest = rest.trim_start();
+            match rest.chars().next()? {
+                ',' => text = &rest[1..],
+                c if c == end => return Some((items, &rest[1..])),
+                _ => return None,
+            }
+        }
+    }
+
+    fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => &members.iter().find(|(k, _)| k == key).unwrap().1,
+            _ => panic!("{:?} is no object", self),
+        }
+    }
+
+    fn number(&self) -> f32 {
+        match self {
+            Json::Number(n) => *n,
+            _ => panic!("{:?} is no number", self),
+        }
+    }
+}
+
+#[test]
+fn test_export_tiles() {
+    use crate::cloud::word_cloud::{test_font_set, test_words};
+    use crate::export::ExportOptions;
+    use crate::{Dimensions, WordCloudBuilder};
+
+    let font_set = test_font_set();
+    let wc = WordCloudBuilder::new()
+        .dimensions(Dimensions::from_wh(600, 450))
+        .font(&font_set)
+        .build()
+        .unwrap();
+    let words = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india",
+        "juliett", "kilo", "lima", "mike", "november", "oscar", "papa",
+    ];
+    let counts = words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (*w, 2 * (words.len() - i)))
+        .collect::<Vec<_>>();
+    wc.write_content(test_words(&counts), words.len()).unwrap();
+
+    let directory = std::env::temp_dir().join(format!("wordcloud-tiles-{}", std::process::id()));
+    let options = TileOptions::new()
+        .grid(3, 2)
+        .rendered_options(RenderedOptions::new().merge_paths(false).word_labels(true));
+    wc.export_tiles(&directory, &options).unwrap();
+    let read = |file: &str| std::fs::read_to_string(directory.join(file)).unwrap();
+    let manifest = Json::parse(&read("manifest.json")).expect("the manifest is no valid JSON");
+    let tiles = match manifest.get("tiles") {
+        Json::Array(tiles) => tiles
+            .iter()
+            .map(|tile| match tile.get("file") {
+                Json::String(file) => (tile, file.clone(), read(file)),
+                _ => panic!("the file of {:?} is no string", tile),
+            })
+            .collect::<Vec<_>>(),
+        _ => panic!("the tiles are no array"),
+    };
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(manifest.get("format"), &Json::String(String::from("svg")));
+    assert_eq!(manifest.get("width").number(), 600.);
+    assert_eq!(manifest.get("height").number(), 450.);
+    assert_eq!(tiles.len(), 6);
+
+    let canvas = wc.canvas(&ExportOptions::default());
+    let ct = wc.ct.read();
+    let mut straddling = 0;
+    for (tile, file, svg) in &tiles {
+        let (column, row) = (tile.get("column").number(), tile.get("row").number());
+        assert_eq!(file, &format!("tile_{}_{}.svg", row, column));
+        let cell = [column * 200., row * 225., 200., 225.];
+        let position = ["x", "y", "width", "height"].map(|key| tile.get(key).number());
+        assert_eq!(position, cell);
+        assert!(svg.contains(&format!(
+            r#"viewBox="{} {} {} {}""#,
+            cell[0], cell[1], cell[2], cell[3]
+        )));
+
+        // every word overlapping the cell is drawn, even if it crosses into the next cell
+        let area = Rect {
+            min: canvas.min
+                + Point {
+                    x: cell[0],
+                    y: cell[1],
+                },
+            max: canvas.min
+                + Point {
+                    x: cell[0] + cell[2],
+                    y: cell[1] + cell[3],
+                },
+        };
+        for word in ct.iter().map(|e| e.value_ref()) {
+            let label = format!(r#"aria-label="{}""#, word.text);
+            assert_eq!(
+                svg.contains(&label),
+                word.bounding_box.overlaps(&area),
+                "{} in {}",
+                word.text,
+                file
+            );
+            if word.bounding_box.overlaps(&area) && !area.contains(&word.bounding_box) {
+                straddling += 1;
+            }
+        }
+    }
+    // a word crossing a border appears in the tiles on both sides
+    assert!(straddling >= 2);
+}
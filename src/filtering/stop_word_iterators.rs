@@ -143,7 +143,7 @@ where
     Trait implementing an [`Iterator`] function to filter out StopWords out of an Iterator
 */
 pub trait StopWordsIterator: Iterator {
-    fn filter_stop_words(self, sw: &StopWords) -> StopWordsIteratorInner<Self>
+    fn filter_stop_words(self, sw: &StopWords) -> StopWordsIteratorInner<'_, Self>
     where
         Self: Sized,
     {
@@ -158,7 +158,7 @@ pub trait StopWordsIterator: Iterator {
    Trait implementing a [`ParallelIterator`] function to filter out StopWords out of a ParallelIterator
 */
 pub trait StopWordsIteratorPar: ParallelIterator {
-    fn filter_stop_words(self, sw: &StopWords) -> StopWordsIteratorParallel<Self>
+    fn filter_stop_words(self, sw: &StopWords) -> StopWordsIteratorParallel<'_, Self>
     where
         Self: Sized,
     {
@@ -4,6 +4,13 @@ use edge_detection::Detection;
 use crate::types::point::Point;
 use crate::types::rect::Rect;
 
+#[cfg(feature = "background_image")]
+mod region;
+#[cfg(feature = "background_image")]
+pub(crate) use region::RegionMap;
+#[cfg(feature = "background_image")]
+pub use region::RegionMask;
+
 #[cfg(feature = "background_image")]
 use image::{DynamicImage, GenericImageView, GrayImage, Rgba};
 
@@ -72,8 +79,3 @@ pub(crate) fn average_color_for_rect(
         default
     }
 }
-
-#[cfg(feature = "background_image")]
-pub(crate) fn color_to_rgb_string(rgba: &Rgba<u8>) -> String {
-    format!("rgb({}, {}, {})", rgba.0[0], rgba.0[1], rgba.0[2],)
-}
@@ -0,0 +1,203 @@
+use crate::types::point::Point;
+use crate::types::rect::Rect;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgba};
+use quadtree_rs::area::Area;
+use std::collections::HashMap;
+
+/// Maximum squared RGB distance between a pixel and a region key color
+const REGION_COLOR_TOLERANCE: u32 = 3 * 48 * 48;
+
+/**
+    A labeled mask image, where each key color marks a region of the WordCloud. Words written
+    with [`crate::WordContent::Regions`] are only placed inside their region. The mask is
+    scaled like the background image, keeping its aspect ratio.
+
+    Example Use:
+    ```no_run
+    use wordcloud::RegionMask;
+    use image::Rgba;
+
+    let mask_image = image::open("mask.png").unwrap();
+    let mask = RegionMask::new(&mask_image)
+        .region(0, Rgba([255, 0, 0, 255]))
+        .region(1, Rgba([0, 0, 255, 255]))
+        .fill(1, Rgba([20, 20, 120, 255]));
+    ```
+*/
+pub struct RegionMask<'a> {
+    image: &'a DynamicImage,
+    keys: Vec<(usize, Rgba<u8>)>,
+    fills: HashMap<usize, Rgba<u8>>,
+}
+
+impl<'a> RegionMask<'a> {
+    /**
+        Create a new [`RegionMask`] from a mask image
+    */
+    pub fn new(image: &'a DynamicImage) -> Self {
+        RegionMask {
+            image,
+            keys: Vec::new(),
+            fills: HashMap::new(),
+        }
+    }
+
+    /**
+        Map all pixels of the mask with (approximately) the `key` color to the region `id`
+    */
+    pub fn region(mut self, id: usize, key: Rgba<u8>) -> Self {
+        self.keys.push((id, key));
+        self
+    }
+
+    /**
+        Color used for the words of the region `id`. Without it, the color is taken from the
+        background image.
+    */
+    pub fn fill(mut self, id: usize, color: Rgba<u8>) -> Self {
+        self.fills.insert(id, color);
+        self
+    }
+
+    fn label_for(&self, pixel: Rgba<u8>) -> Option<usize> {
+        self.keys
+            .iter()
+            .map(|(id, key)| {
+                let distance = (0..3)
+                    .map(|c| (pixel.0[c] as i32 - key.0[c] as i32).pow(2) as u32)
+                    .sum::<u32>();
+                (distance, *id)
+            })
+            .filter(|(distance, _)| *distance <= REGION_COLOR_TOLERANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, id)| id)
+    }
+
+    /**
+        Rasterizes the mask into a label grid, which fits into the given size. Like the
+        background image, the mask keeps its aspect ratio, so both grids line up.
+    */
+    pub(crate) fn to_map(&self, width: u32, height: u32) -> RegionMap {
+        let resized = self.image.resize(width, height, FilterType::Nearest);
+        let (width, height) = (resized.width(), resized.height());
+
+        let labels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let pixel = resized.get_pixel(x, y);
+                // transparent pixels never belong to a region
+                if pixel.0[3] == 0 {
+                    None
+                } else {
+                    self.label_for(pixel)
+                }
+            })
+            .collect::<Vec<Option<usize>>>();
+
+        let mut bounds: HashMap<usize, Rect<u64>> = HashMap::new();
+        for (i, label) in labels.iter().enumerate() {
+            if let Some(id) = label {
+                let cell = Point {
+                    x: i as u64 % width as u64,
+                    y: i as u64 / width as u64,
+                };
+                let cell_end = Point {
+                    x: cell.x + 1,
+                    y: cell.y + 1,
+                };
+                bounds
+                    .entry(*id)
+                    .and_modify(|r| {
+                        r.min = r.min.min(&cell);
+                        r.max = r.max.max(&cell_end);
+                    })
+                    .or_insert(Rect {
+                        min: cell,
+                        max: cell_end,
+                    });
+            }
+        }
+
+        RegionMap {
+            width: width as u64,
+            height: height as u64,
+            labels,
+            bounds,
+            fills: self.fills.clone(),
+        }
+    }
+}
+
+/**
+    Region labels of a [`RegionMask`] in quadtree coordinates
+*/
+pub(crate) struct RegionMap {
+    width: u64,
+    height: u64,
+    labels: Vec<Option<usize>>,
+    bounds: HashMap<usize, Rect<u64>>,
+    fills: HashMap<usize, Rgba<u8>>,
+}
+
+impl RegionMap {
+    /**
+        Checks, if every cell of the `area` belongs to the region `id`
+    */
+    pub(crate) fn area_inside(&self, area: &Area<u64>, id: usize) -> bool {
+        let (anchor, w, h) = (area.anchor(), area.width(), area.height());
+        if anchor.x + w > self.width || anchor.y + h > self.height {
+            return false;
+        }
+
+        (anchor.y..anchor.y + h).all(|y| {
+            (anchor.x..anchor.x + w).all(|x| self.labels[(y * self.width + x) as usize] == Some(id))
+        })
+    }
+
+    /**
+        Bounding box of the region `id` in quadtree coordinates
+    */
+    pub(crate) fn bounds(&self, id: usize) -> Option<&Rect<u64>> {
+        self.bounds.get(&id)
+    }
+
+    pub(crate) fn fill(&self, id: usize) -> Option<&Rgba<u8>> {
+        self.fills.get(&id)
+    }
+}
+
+#[test]
+fn test_region_map() {
+    use image::RgbaImage;
+    use quadtree_rs::area::AreaBuilder;
+
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 10, |x, _| {
+        if x < 5 {
+            Rgba([250, 10, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    }));
+    let map = RegionMask::new(&image)
+        .region(0, Rgba([255, 0, 0, 255]))
+        .region(1, Rgba([0, 0, 255, 255]))
+        .to_map(10, 10);
+
+    let left = AreaBuilder::default()
+        .anchor((1, 1).into())
+        .dimensions((3, 8))
+        .build()
+        .unwrap();
+    let across = AreaBuilder::default()
+        .anchor((3, 1).into())
+        .dimensions((4, 2))
+        .build()
+        .unwrap();
+
+    assert!(map.area_inside(&left, 0));
+    assert!(!map.area_inside(&left, 1));
+    assert!(!map.area_inside(&across, 0));
+    assert!(!map.area_inside(&across, 1));
+    assert_eq!(map.bounds(1).unwrap().min.x, 5);
+}
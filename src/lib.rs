@@ -10,7 +10,8 @@
    let mut font_data = Vec::from(include_bytes!("font.ttf") as &[u8]);
    let font_set = FontSetBuilder::new()
        .push(Font::from_data(&mut font_data).unwrap())
-       .build();
+       .build()
+       .unwrap();
 
    let output_dimensions = Dimensions::from_wh(1000, 1000);
    let wc = WordCloudBuilder::new()
@@ -22,7 +23,7 @@
    let input_text = io::read_string_from_file("input_text.txt").unwrap();
    let ranked = RankedWords::rank(input_text.split_whitespace().collect());
 
-   wc.write_content(ranked, 2000);
+   wc.write_content(ranked, 2000).unwrap();
    wc.export_rendered_to_file("output.svg").unwrap();
    ```
 
@@ -75,6 +76,7 @@
 
 mod cloud;
 mod common;
+mod export;
 mod filtering;
 mod image;
 
@@ -85,11 +87,21 @@ pub mod io;
 mod rank;
 
 pub use cloud::*;
+pub use export::html::HtmlOptions;
+pub use export::pdf::{PageSize, PdfContent, PdfOptions};
+pub use export::plotter::PlotterOptions;
+#[cfg(feature = "raster")]
+pub use export::raster::RasterOptions;
+pub use export::tiles::{TileFormat, TileOptions};
+pub use export::{Background, ExportOptions, RenderedOptions, XmlFormatting};
 pub use filtering::*;
 
+pub use common::color;
 pub use common::font;
 
 pub use crate::image::Dimensions;
+#[cfg(feature = "background_image")]
+pub use crate::image::RegionMask;
 pub use rank::RankedWords;
 
 mod types;
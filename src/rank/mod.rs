@@ -24,7 +24,8 @@ pub struct RankedWords(pub(crate) Vec<Token>);
 
 impl RankedWords {
     /**
-        Rank the words by accuracy.
+        Rank the words by accuracy. Words with the same count are ranked alphabetically, so
+        the ranks don't depend on the order of `words`.
     */
     pub fn rank(words: Vec<String>) -> RankedWords {
         let mut hs: HashMap<String, usize> = HashMap::new();
@@ -42,7 +43,12 @@ impl RankedWords {
             })
             .collect::<Vec<Token>>();
 
-        n.sort_by(|w, w2| w2.count.cmp(&w.count));
+        // words with the same count are ordered by their text, the order of the map is random
+        n.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.content.cmp(&b.content))
+        });
 
         RankedWords(n)
     }
@@ -1,4 +1,5 @@
 pub(crate) mod point;
+pub(crate) mod polyline;
 pub(crate) mod rect;
 pub(crate) mod rotation;
 pub(crate) mod spiral;
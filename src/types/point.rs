@@ -53,6 +53,16 @@ where
     }
 }
 
+impl Point<f32> {
+    pub(crate) fn distance(&self, other: &Point<f32>) -> f32 {
+        (*self - *other).length()
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+}
+
 impl<T> Mul<T> for Point<T>
 where
     T: Mul<Output = T> + Copy,
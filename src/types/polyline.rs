@@ -0,0 +1,88 @@
+use crate::types::point::Point;
+
+/**
+    An ordered chain of points, which can be sampled by arc length.
+*/
+#[derive(Clone, Debug)]
+pub(crate) struct Polyline {
+    points: Vec<Point<f32>>,
+    distances: Vec<f32>,
+}
+
+impl Polyline {
+    pub(crate) fn new(points: Vec<Point<f32>>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut travelled = 0.;
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                travelled += p.distance(&points[i - 1]);
+            }
+            distances.push(travelled);
+        }
+
+        Polyline { points, distances }
+    }
+
+    /**
+        Averages every point with its neighbours, which removes the staircase pattern of
+        pixel based contours.
+    */
+    pub(crate) fn smoothed(&self, window: usize) -> Self {
+        let len = self.points.len();
+        let smoothed = (0..len)
+            .map(|i| {
+                let from = i.saturating_sub(window);
+                let to = usize::min(i + window, len - 1);
+                let sum = self.points[from..=to]
+                    .iter()
+                    .fold(Point::default(), |acc: Point<f32>, p| acc + *p);
+
+                sum * (1. / (to - from + 1) as f32)
+            })
+            .collect();
+
+        Polyline::new(smoothed)
+    }
+
+    pub(crate) fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.)
+    }
+
+    /**
+        Returns the point at the arc length `distance`, measured from the first point, or
+        `None` if the polyline has no points.
+    */
+    pub(crate) fn point_at(&self, distance: f32) -> Option<Point<f32>> {
+        let idx = self.distances.partition_point(|d| *d < distance);
+        if idx == 0 {
+            return self.points.first().copied();
+        }
+        if idx >= self.points.len() {
+            return self.points.last().copied();
+        }
+
+        let (d0, d1) = (self.distances[idx - 1], self.distances[idx]);
+        let t = if d1 > d0 {
+            (distance - d0) / (d1 - d0)
+        } else {
+            0.
+        };
+
+        Some(self.points[idx - 1] + (self.points[idx] - self.points[idx - 1]) * t)
+    }
+}
+
+#[test]
+fn test_polyline_sampling() {
+    let line = Polyline::new(vec![(0., 0.).into(), (10., 0.).into(), (10., 10.).into()]);
+
+    assert_eq!(line.length(), 20.);
+    assert_eq!(line.point_at(5.), Some((5., 0.).into()));
+    assert_eq!(line.point_at(15.), Some((10., 5.).into()));
+    assert_eq!(line.point_at(25.), Some((10., 10.).into()));
+
+    let empty = Polyline::new(Vec::new());
+    assert_eq!(empty.length(), 0.);
+    assert_eq!(empty.point_at(0.), None);
+    assert_eq!(empty.smoothed(2).point_at(1.), None);
+}
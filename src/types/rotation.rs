@@ -9,21 +9,26 @@ pub(crate) enum Rotation {
     Ninety,
     OneEighty,
     TwoSeventy,
+    /// Arbitrary clockwise rotation in degrees, used when words follow a contour
+    Custom(f32),
+    /// Upright glyphs stacked from top to bottom, the vertical writing mode of CJK text
+    Vertical,
 }
 
 impl Rotation {
-    pub(crate) fn inner(&self) -> i64 {
+    pub(crate) fn inner(&self) -> f32 {
         match self {
-            Rotation::Zero => 0,
-            Rotation::Ninety => 90,
-            Rotation::OneEighty => 180,
-            Rotation::TwoSeventy => 270,
+            Rotation::Zero | Rotation::Vertical => 0.,
+            Rotation::Ninety => 90.,
+            Rotation::OneEighty => 180.,
+            Rotation::TwoSeventy => 270.,
+            Rotation::Custom(deg) => *deg,
         }
     }
 
     pub(crate) fn rotate_point(&self, point: Point<f32>) -> Point<f32> {
         match self {
-            Rotation::Zero => point,
+            Rotation::Zero | Rotation::Vertical => point,
             Rotation::Ninety => Point {
                 x: -point.y,
                 y: point.x,
@@ -36,12 +41,19 @@ impl Rotation {
                 x: point.y,
                 y: -point.x,
             },
+            Rotation::Custom(deg) => {
+                let (sin, cos) = deg.to_radians().sin_cos();
+                Point {
+                    x: point.x * cos - point.y * sin,
+                    y: point.x * sin + point.y * cos,
+                }
+            }
         }
     }
 
     pub(crate) fn rotate_point_back(&self, point: &Point<f32>) -> Point<f32> {
         match self {
-            Rotation::Zero => *point,
+            Rotation::Zero | Rotation::Vertical => *point,
             Rotation::Ninety => Point {
                 x: point.y,
                 y: -point.x,
@@ -54,19 +66,35 @@ impl Rotation {
                 x: -point.y,
                 y: point.x,
             },
+            Rotation::Custom(deg) => Rotation::Custom(-deg).rotate_point(*point),
         }
     }
 
     pub(crate) fn rotate_rectangle(&self, rect: Rect<f32>) -> Rect<f32> {
-        let (rot_min, rot_max) = (self.rotate_point(rect.min), self.rotate_point(rect.max));
+        let corners = match self {
+            Rotation::Custom(_) => vec![
+                self.rotate_point(rect.min),
+                self.rotate_point(rect.max),
+                self.rotate_point(Point {
+                    x: rect.min.x,
+                    y: rect.max.y,
+                }),
+                self.rotate_point(Point {
+                    x: rect.max.x,
+                    y: rect.min.y,
+                }),
+            ],
+            _ => vec![self.rotate_point(rect.min), self.rotate_point(rect.max)],
+        };
+
         Rect {
             min: Point {
-                x: rot_min.x.min(rot_max.x),
-                y: rot_min.y.min(rot_max.y),
+                x: corners.iter().map(|p| p.x).fold(f32::MAX, f32::min),
+                y: corners.iter().map(|p| p.y).fold(f32::MAX, f32::min),
             },
             max: Point {
-                x: rot_min.x.max(rot_max.x),
-                y: rot_min.y.max(rot_max.y),
+                x: corners.iter().map(|p| p.x).fold(f32::MIN, f32::max),
+                y: corners.iter().map(|p| p.y).fold(f32::MIN, f32::max),
             },
         }
     }